@@ -0,0 +1,9 @@
+use std::net::SocketAddr;
+
+/// 连接的本地地址，由服务器保存在请求扩展中。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LocalAddr(pub SocketAddr);
+
+/// 连接的对端地址，由服务器保存在请求扩展中。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RemoteAddr(pub SocketAddr);
@@ -1,6 +1,12 @@
 #![forbid(unsafe_code)]
 #![deny(missing_debug_implementations)]
 
+mod addr;
+pub use addr::{LocalAddr, RemoteAddr};
+
+mod quality;
+pub use quality::{encoding_quality, parse_accept_encoding, parse_quality};
+
 pub mod response;
 pub use response::Response;
 
@@ -0,0 +1,37 @@
+use http::{header, HeaderMap};
+
+/// 解析`Accept`、`Accept-Encoding`等请求头中`q=`参数的值，返回千分制的权重。
+///
+/// 值不是0到1之间的数字时返回`None`。
+pub fn parse_quality(q: &str) -> Option<u16> {
+    let q = q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?;
+    Some((q * 1000.0).round() as u16)
+}
+
+/// 解析`Accept-Encoding`，返回编码名称和千分制的权重，权重无效的项被忽略。
+pub fn parse_accept_encoding(headers: &HeaderMap) -> Vec<(&str, u16)> {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|v| {
+            let mut parts = v.split(';');
+            let coding = parts.next()?.trim();
+            let q = match parts.find_map(|p| p.trim().strip_prefix("q=")) {
+                Some(q) => parse_quality(q)?,
+                None => 1000,
+            };
+            (!coding.is_empty()).then_some((coding, q))
+        })
+        .collect()
+}
+
+/// [`parse_accept_encoding`]结果中`name`的权重，未列出时取`*`的权重，都没有时为0。
+pub fn encoding_quality(accepted: &[(&str, u16)], name: &str) -> u16 {
+    accepted
+        .iter()
+        .find(|(coding, _)| coding.eq_ignore_ascii_case(name))
+        .or_else(|| accepted.iter().find(|(coding, _)| *coding == "*"))
+        .map_or(0, |(_, q)| *q)
+}
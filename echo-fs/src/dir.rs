@@ -0,0 +1,361 @@
+use std::convert::Infallible;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use echo_core::http::{header, HeaderMap, HeaderValue, StatusCode};
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::Service;
+use echo_core::{Request, Response};
+use percent_encoding::percent_decode_str;
+
+use crate::serve::{io_error, method_not_allowed, serve_file, Options};
+
+/// 响应目录中文件的服务，通常通过`Router::scope`挂载。
+///
+/// 请求路径中的`..`等会离开目录的部分会被拒绝并返回404。
+#[derive(Debug, Clone)]
+pub struct ServeDir {
+    base: Arc<PathBuf>,
+    index: Option<Arc<str>>,
+    options: Options,
+}
+
+impl ServeDir {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            base: Arc::new(path.into()),
+            index: Some("index.html".into()),
+            options: Default::default(),
+        }
+    }
+
+    /// 请求目录时响应的文件，默认为`index.html`。
+    pub fn index_file(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.index = Some(name.into());
+        self
+    }
+
+    /// 请求目录时返回404。
+    pub fn disable_index(mut self) -> Self {
+        self.index = None;
+        self
+    }
+
+    /// 客户端接受`gzip`时，优先响应同目录下的`<file>.gz`。
+    pub fn precompressed_gzip(mut self) -> Self {
+        self.options.precompressed.gzip = true;
+        self
+    }
+
+    /// 客户端接受`br`时，优先响应同目录下的`<file>.br`。
+    pub fn precompressed_br(mut self) -> Self {
+        self.options.precompressed.br = true;
+        self
+    }
+
+    /// 每次读取文件的最大字节数。
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.options.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl Service<Request> for ServeDir {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn call(&self, request: Request) -> Self::Future {
+        let base = self.base.clone();
+        let index = self.index.clone();
+        let options = self.options;
+        let (parts, _) = request.into_parts();
+
+        Box::pin(async move {
+            if let Some(response) = method_not_allowed(&parts.method) {
+                return Ok(response);
+            }
+
+            let request_path = parts.uri.path();
+            let Some(mut path) = resolve(&base, request_path) else {
+                return Ok(StatusCode::NOT_FOUND.into_response());
+            };
+
+            match tokio::fs::metadata(&path).await {
+                Ok(metadata) if metadata.is_dir() => {
+                    let Some(index) = index else {
+                        return Ok(StatusCode::NOT_FOUND.into_response());
+                    };
+                    if !request_path.ends_with('/') {
+                        return Ok(redirect_to_dir(request_path, parts.uri.query()));
+                    }
+                    path.push(index.as_ref());
+                }
+                Ok(_) => {}
+                // 原文件不存在时仍可能存在预压缩文件。
+                Err(error) if !options.precompressed.is_enabled() => return Ok(io_error(error)),
+                Err(_) => {}
+            }
+
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            Ok(serve_file(parts.method, parts.headers, &path, mime, options).await)
+        })
+    }
+}
+
+/// 将请求路径映射到`base`下的文件。
+fn resolve(base: &Path, request_path: &str) -> Option<PathBuf> {
+    let relative = relative_path(request_path)?;
+    let mut path = base.to_path_buf();
+    path.extend(relative.split('/').filter(|s| !s.is_empty()));
+    Some(path)
+}
+
+/// 解码并规范化请求路径，返回以`/`分隔的相对路径，拒绝任何可能离开根目录的路径。
+pub(crate) fn relative_path(request_path: &str) -> Option<String> {
+    let decoded = percent_decode_str(request_path).decode_utf8().ok()?;
+
+    let mut segments = Vec::new();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            segment if segment.contains(['\\', '\0']) => return None,
+            segment => {
+                let mut components = Path::new(segment).components();
+                if !matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                ) {
+                    return None;
+                }
+                segments.push(segment);
+            }
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// 目录请求缺少结尾的`/`时重定向，使页面中的相对链接能正确解析。
+///
+/// 使用相对的`Location`，因此在嵌套路由中也不需要知道前缀。
+pub(crate) fn redirect_to_dir(request_path: &str, query: Option<&str>) -> Response {
+    let name = request_path.rsplit('/').next().unwrap_or_default();
+    let location = match query {
+        Some(query) => format!("{name}/?{query}"),
+        None => format!("{name}/"),
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Ok(location) = HeaderValue::try_from(location) {
+        headers.insert(header::LOCATION, location);
+    }
+    (StatusCode::PERMANENT_REDIRECT, headers).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::{header, Method, StatusCode};
+    use echo_core::service::Service;
+    use echo_core::{Request, Response};
+
+    use super::{resolve, ServeDir};
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echo-fs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("hello.txt"), "hello world").unwrap();
+        std::fs::write(dir.join("hello.txt.gz"), "gzipped").unwrap();
+        std::fs::write(dir.join("sub/index.html"), "<p>index</p>").unwrap();
+        dir
+    }
+
+    fn request(method: Method, uri: &str, headers: &[(header::HeaderName, &str)]) -> Request {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(name, *value);
+        }
+        builder.body(Default::default()).unwrap()
+    }
+
+    async fn body(response: Response) -> String {
+        let body = response.into_body().collect().bytes().await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn traversal() {
+        let base = Path::new("/srv");
+        assert_eq!(resolve(base, "/a/./b"), Some(PathBuf::from("/srv/a/b")));
+        assert_eq!(resolve(base, "/a%20b"), Some(PathBuf::from("/srv/a b")));
+        assert_eq!(resolve(base, "/../etc/passwd"), None);
+        assert_eq!(resolve(base, "/a/%2e%2e/%2e%2e/etc"), None);
+        assert_eq!(resolve(base, "/a%5c..%5cb"), None);
+    }
+
+    #[tokio::test]
+    async fn serve() {
+        let base = dir("serve");
+        let service = ServeDir::new(&base);
+
+        let response = service
+            .call(request(Method::GET, "/hello.txt", &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "11");
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_eq!(body(response).await, "hello world");
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[(header::IF_NONE_MATCH, &etag)],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = service
+            .call(request(Method::HEAD, "/hello.txt", &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "11");
+        assert_eq!(body(response).await, "");
+
+        let response = service
+            .call(request(Method::GET, "/sub", &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "sub/");
+
+        let response = service
+            .call(request(Method::GET, "/sub/", &[]))
+            .await
+            .unwrap();
+        assert_eq!(body(response).await, "<p>index</p>");
+
+        let response = service
+            .call(request(Method::GET, "/../hello.txt", &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = service
+            .call(request(Method::POST, "/hello.txt", &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[tokio::test]
+    async fn ranges() {
+        let base = dir("ranges");
+        let service = ServeDir::new(&base).chunk_size(4);
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[(header::RANGE, "bytes=6-")],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 6-10/11");
+        assert_eq!(body(response).await, "world");
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[(header::RANGE, "bytes=0-1,-2")],
+            ))
+            .await
+            .unwrap();
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+        let length = response.headers()[header::CONTENT_LENGTH].to_str().unwrap();
+        let length = length.parse::<usize>().unwrap();
+        let body = body(response).await;
+        assert_eq!(body.len(), length);
+        assert_eq!(
+            body,
+            format!(
+                "--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/11\r\n\r\nhe\
+                 \r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 9-10/11\r\n\r\nld\
+                 \r\n--{boundary}--\r\n"
+            )
+        );
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[(header::RANGE, "bytes=20-")],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */11");
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[
+                    (header::RANGE, "bytes=0-1"),
+                    (header::IF_RANGE, "\"stale\""),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[tokio::test]
+    async fn precompressed() {
+        let base = dir("precompressed");
+        let service = ServeDir::new(&base).precompressed_gzip().precompressed_br();
+
+        let response = service
+            .call(request(
+                Method::GET,
+                "/hello.txt",
+                &[(header::ACCEPT_ENCODING, "gzip, br")],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+        assert_eq!(body(response).await, "gzipped");
+
+        let response = service
+            .call(request(Method::GET, "/hello.txt", &[]))
+            .await
+            .unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(body(response).await, "hello world");
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
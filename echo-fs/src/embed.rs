@@ -0,0 +1,342 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use echo_core::body::Bytes;
+use echo_core::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::Service;
+use echo_core::{Request, Response};
+
+use crate::dir::{redirect_to_dir, relative_path};
+use crate::encoding::{Encoding, Precompressed};
+use crate::serve::method_not_allowed;
+use crate::ServeDir;
+
+/// 编译时嵌入的文件，由`embed_dir!`生成。
+#[derive(Debug)]
+pub struct EmbeddedFile {
+    path: &'static str,
+    contents: &'static [u8],
+    gzip: Option<&'static [u8]>,
+    br: Option<&'static [u8]>,
+    etag: &'static str,
+    content_type: &'static str,
+}
+
+impl EmbeddedFile {
+    #[doc(hidden)]
+    pub const fn new(
+        path: &'static str,
+        contents: &'static [u8],
+        gzip: Option<&'static [u8]>,
+        br: Option<&'static [u8]>,
+        etag: &'static str,
+        content_type: &'static str,
+    ) -> Self {
+        Self {
+            path,
+            contents,
+            gzip,
+            br,
+            etag,
+            content_type,
+        }
+    }
+
+    /// 相对于嵌入目录的路径，使用`/`分隔。
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    pub fn contents(&self) -> &'static [u8] {
+        self.contents
+    }
+
+    pub fn etag(&self) -> &'static str {
+        self.etag
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+
+    fn variant(&self, encoding: Encoding) -> Option<&'static [u8]> {
+        match encoding {
+            Encoding::Brotli => self.br,
+            Encoding::Gzip => self.gzip,
+        }
+    }
+}
+
+/// 编译时嵌入的目录，由`embed_dir!`生成，按路径排序。
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedDir {
+    root: &'static str,
+    files: &'static [EmbeddedFile],
+}
+
+impl EmbeddedDir {
+    #[doc(hidden)]
+    pub const fn new(root: &'static str, files: &'static [EmbeddedFile]) -> Self {
+        Self { root, files }
+    }
+
+    /// 嵌入时目录在磁盘上的绝对路径。
+    pub fn root(&self) -> &'static str {
+        self.root
+    }
+
+    pub fn files(&self) -> &'static [EmbeddedFile] {
+        self.files
+    }
+
+    pub fn get(&self, path: &str) -> Option<&'static EmbeddedFile> {
+        let files = self.files;
+        files
+            .binary_search_by(|file| file.path.cmp(path))
+            .ok()
+            .map(|i| &files[i])
+    }
+
+    pub fn into_service(self) -> ServeEmbedded {
+        ServeEmbedded::new(self)
+    }
+}
+
+/// 响应[`EmbeddedDir`]中文件的服务，与[`ServeDir`]一样通过`Router::scope`挂载。
+#[derive(Debug, Clone)]
+pub struct ServeEmbedded {
+    dir: EmbeddedDir,
+    index: Option<Arc<str>>,
+    disk: Option<ServeDir>,
+}
+
+impl ServeEmbedded {
+    pub fn new(dir: EmbeddedDir) -> Self {
+        Self {
+            dir,
+            index: Some("index.html".into()),
+            disk: None,
+        }
+    }
+
+    /// 请求目录时响应的文件，默认为`index.html`。
+    pub fn index_file(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.index = Some(name.into());
+        self.disk = self
+            .disk
+            .map(|disk| disk.index_file(self.index.clone().unwrap()));
+        self
+    }
+
+    /// 请求目录时返回404。
+    pub fn disable_index(mut self) -> Self {
+        self.index = None;
+        self.disk = self.disk.map(ServeDir::disable_index);
+        self
+    }
+
+    /// 开发模式下直接从[`EmbeddedDir::root`]读取文件，修改文件后不需要重新编译。
+    pub fn development(mut self, enable: bool) -> Self {
+        self.disk = enable.then(|| {
+            let disk = ServeDir::new(self.dir.root)
+                .precompressed_gzip()
+                .precompressed_br();
+            match &self.index {
+                Some(index) => disk.index_file(index.clone()),
+                None => disk.disable_index(),
+            }
+        });
+        self
+    }
+
+    fn serve(&self, request: Request) -> Response {
+        if let Some(response) = method_not_allowed(request.method()) {
+            return response;
+        }
+
+        let Some(mut path) = relative_path(request.uri().path()) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+
+        let is_dir = path.is_empty() || request.uri().path().ends_with('/');
+        if is_dir || self.dir.get(&path).is_none() {
+            let Some(index) = &self.index else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            let index = if path.is_empty() {
+                index.to_string()
+            } else {
+                format!("{path}/{index}")
+            };
+            if self.dir.get(&index).is_none() {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+            if !is_dir {
+                return redirect_to_dir(request.uri().path(), request.uri().query());
+            }
+            path = index;
+        }
+
+        let file = self.dir.get(&path).unwrap();
+        serve_embedded(request.method(), request.headers(), file)
+    }
+}
+
+impl Service<Request> for ServeEmbedded {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn call(&self, request: Request) -> Self::Future {
+        if let Some(disk) = &self.disk {
+            return disk.call(request);
+        }
+        let response = self.serve(request);
+        Box::pin(std::future::ready(Ok(response)))
+    }
+}
+
+fn serve_embedded(method: &Method, headers: &HeaderMap, file: &EmbeddedFile) -> Response {
+    let precompressed = Precompressed {
+        br: file.br.is_some(),
+        gzip: file.gzip.is_some(),
+    };
+
+    let variant = precompressed
+        .accepted(headers)
+        .into_iter()
+        .find_map(|encoding| Some((encoding, file.variant(encoding)?)));
+    let encoding = variant.map(|(encoding, _)| encoding);
+    let etag = etag(file, encoding);
+
+    let mut response_headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::try_from(etag.as_str()) {
+        response_headers.insert(header::ETAG, value);
+    }
+    if precompressed.is_enabled() {
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|list| {
+            list.split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+        });
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    let contents = match variant {
+        Some((encoding, contents)) => {
+            response_headers.insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.name()),
+            );
+            contents
+        }
+        None => file.contents,
+    };
+
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(file.content_type),
+    );
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(contents.len()));
+
+    let mut response = (StatusCode::OK, response_headers).into_response();
+    if method != Method::HEAD {
+        *response.body_mut() = Bytes::from_static(contents).into_response().into_body();
+    }
+    response
+}
+
+/// 与磁盘文件一样，压缩后的内容在`ETag`末尾加上编码名称，避免缓存混用不同编码的内容。
+fn etag(file: &EmbeddedFile, encoding: Option<Encoding>) -> String {
+    match encoding {
+        Some(encoding) => {
+            let tag = file.etag.strip_suffix('"').unwrap_or(file.etag);
+            format!("{tag}-{}\"", encoding.name())
+        }
+        None => file.etag.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::body::BodyExt;
+    use echo_core::http::{header, Method, StatusCode};
+    use echo_core::service::Service;
+    use echo_core::Request;
+
+    use super::{EmbeddedDir, EmbeddedFile};
+
+    static FILES: &[EmbeddedFile] = &[
+        EmbeddedFile::new(
+            "app.js",
+            b"console.log(1)",
+            Some(b"gzipped"),
+            None,
+            "\"1\"",
+            "text/javascript",
+        ),
+        EmbeddedFile::new("docs/index.html", b"docs", None, None, "\"2\"", "text/html"),
+        EmbeddedFile::new("index.html", b"home", None, None, "\"3\"", "text/html"),
+    ];
+
+    fn request(uri: &str, headers: &[(header::HeaderName, &str)]) -> Request {
+        let mut builder = Request::builder().method(Method::GET).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(name, *value);
+        }
+        builder.body(Default::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serve() {
+        let service = EmbeddedDir::new("/nonexistent", FILES).into_service();
+
+        let response = service.call(request("/", &[])).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "home");
+
+        let response = service.call(request("/docs", &[])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+
+        let response = service
+            .call(request("/app.js", &[(header::ACCEPT_ENCODING, "gzip")]))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::ETAG], "\"1-gzip\"");
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "gzipped");
+
+        let response = service
+            .call(request("/app.js", &[(header::IF_NONE_MATCH, "\"1\"")]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // 不同编码的`ETag`不同，缓存的原始内容不能用于压缩后的请求。
+        let response = service
+            .call(request(
+                "/app.js",
+                &[
+                    (header::IF_NONE_MATCH, "\"1\""),
+                    (header::ACCEPT_ENCODING, "gzip"),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = service.call(request("/missing", &[])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,90 @@
+use std::cmp::Reverse;
+
+use echo_core::http::HeaderMap;
+use echo_core::{encoding_quality, parse_accept_encoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// 预压缩文件相对原文件的后缀。
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => ".br",
+            Encoding::Gzip => ".gz",
+        }
+    }
+}
+
+/// 启用的预压缩格式。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Precompressed {
+    pub(crate) br: bool,
+    pub(crate) gzip: bool,
+}
+
+impl Precompressed {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.br || self.gzip
+    }
+
+    /// 客户端接受的预压缩格式，按权重从高到低排列，权重相同时优先`br`。
+    pub(crate) fn accepted(&self, headers: &HeaderMap) -> Vec<Encoding> {
+        let accepted = parse_accept_encoding(headers);
+        let enabled = [(Encoding::Brotli, self.br), (Encoding::Gzip, self.gzip)];
+        let mut encodings = enabled
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(encoding, _)| (encoding, encoding_quality(&accepted, encoding.name())))
+            .filter(|(_, q)| *q > 0)
+            .collect::<Vec<_>>();
+        encodings.sort_by_key(|(_, q)| Reverse(*q));
+        encodings
+            .into_iter()
+            .map(|(encoding, _)| encoding)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::http::{header, HeaderMap, HeaderValue};
+
+    use super::{Encoding, Precompressed};
+
+    fn accepted(value: &'static str) -> Vec<Encoding> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, HeaderValue::from_static(value));
+        let precompressed = Precompressed {
+            br: true,
+            gzip: true,
+        };
+        precompressed.accepted(&headers)
+    }
+
+    #[test]
+    fn accept_encoding() {
+        assert_eq!(accepted("gzip, br"), [Encoding::Brotli, Encoding::Gzip]);
+        assert_eq!(
+            accepted("gzip, br;q=0.5"),
+            [Encoding::Gzip, Encoding::Brotli]
+        );
+        assert_eq!(accepted("gzip;q=0, *"), [Encoding::Brotli]);
+        assert_eq!(accepted("identity"), []);
+        // 权重无效的项被忽略，而不是当作0。
+        assert_eq!(
+            accepted("br;q=2, *;q=0.5"),
+            [Encoding::Brotli, Encoding::Gzip]
+        );
+    }
+}
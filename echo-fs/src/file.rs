@@ -0,0 +1,74 @@
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use echo_core::service::future::BoxFuture;
+use echo_core::service::Service;
+use echo_core::{Request, Response};
+use mime::Mime;
+
+use crate::serve::{serve_file, Options};
+
+/// 响应单个文件的服务。
+#[derive(Debug, Clone)]
+pub struct ServeFile {
+    path: Arc<PathBuf>,
+    mime: Mime,
+    options: Options,
+}
+
+impl ServeFile {
+    /// 根据扩展名推断`Content-Type`，无法推断时为`application/octet-stream`。
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        Self {
+            path: Arc::new(path),
+            mime,
+            options: Default::default(),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If `mime` isn't a valid media type.
+    pub fn content_type(mut self, mime: &str) -> Self {
+        self.mime = mime.parse().expect("invalid media type");
+        self
+    }
+
+    /// 客户端接受`gzip`时，优先响应同目录下的`<file>.gz`。
+    pub fn precompressed_gzip(mut self) -> Self {
+        self.options.precompressed.gzip = true;
+        self
+    }
+
+    /// 客户端接受`br`时，优先响应同目录下的`<file>.br`。
+    pub fn precompressed_br(mut self) -> Self {
+        self.options.precompressed.br = true;
+        self
+    }
+
+    /// 每次读取文件的最大字节数。
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.options.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl Service<Request> for ServeFile {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn call(&self, request: Request) -> Self::Future {
+        let path = self.path.clone();
+        let mime = self.mime.clone();
+        let options = self.options;
+        let (parts, _) = request.into_parts();
+
+        Box::pin(
+            async move { Ok(serve_file(parts.method, parts.headers, &path, mime, options).await) },
+        )
+    }
+}
@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+#![deny(missing_debug_implementations)]
+
+mod dir;
+mod embed;
+mod encoding;
+mod file;
+mod range;
+mod serve;
+
+pub use dir::ServeDir;
+pub use embed::{EmbeddedDir, EmbeddedFile, ServeEmbedded};
+pub use file::ServeFile;
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+/// 一个请求中最多处理的范围数量，超过时忽略`Range`头，返回完整内容。
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum RangeError {
+    /// 格式错误或不支持的单位，按RFC 7233应忽略`Range`头。
+    Invalid,
+    /// 没有可满足的范围，返回416。
+    Unsatisfiable,
+}
+
+/// 解析`Range: bytes=...`，返回长度为`len`的内容中可满足的范围。
+///
+/// 重叠或相邻的范围会按起始位置排序后合并，避免重复发送同一段内容。
+pub(crate) fn parse_range(header: &str, len: u64) -> Result<Vec<Range<u64>>, RangeError> {
+    let specs = header
+        .trim()
+        .strip_prefix("bytes=")
+        .ok_or(RangeError::Invalid)?;
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (start, end) = spec.split_once('-').ok_or(RangeError::Invalid)?;
+        let range = match (start.trim(), end.trim()) {
+            ("", suffix) => {
+                let suffix = parse_u64(suffix)?;
+                len.saturating_sub(suffix)..len
+            }
+            (start, "") => parse_u64(start)?..len,
+            (start, end) => {
+                let (start, end) = (parse_u64(start)?, parse_u64(end)?);
+                if end < start {
+                    return Err(RangeError::Invalid);
+                }
+                start..end.saturating_add(1).min(len)
+            }
+        };
+        if range.start < range.end {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.len() > MAX_RANGES {
+        return Err(RangeError::Invalid);
+    }
+    if ranges.is_empty() {
+        return Err(RangeError::Unsatisfiable);
+    }
+    Ok(coalesce(ranges))
+}
+
+fn coalesce(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn parse_u64(s: &str) -> Result<u64, RangeError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RangeError::Invalid);
+    }
+    s.parse().map_err(|_| RangeError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_range, RangeError};
+
+    fn bounds(header: &str) -> Vec<(u64, u64)> {
+        let ranges = parse_range(header, 10).unwrap();
+        ranges.into_iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(bounds("bytes=0-4"), [(0, 5)]);
+        assert_eq!(bounds("bytes=5-"), [(5, 10)]);
+        assert_eq!(bounds("bytes=-3"), [(7, 10)]);
+        assert_eq!(bounds("bytes=8-20"), [(8, 10)]);
+        assert_eq!(bounds("bytes=0-1, 4-5"), [(0, 2), (4, 6)]);
+    }
+
+    #[test]
+    fn coalesce() {
+        assert_eq!(bounds("bytes=4-5, 0-1"), [(0, 2), (4, 6)]);
+        assert_eq!(bounds("bytes=0-4, 2-6"), [(0, 7)]);
+        assert_eq!(bounds("bytes=0-1, 2-3"), [(0, 4)]);
+        assert_eq!(bounds("bytes=0-, -3"), [(0, 10)]);
+        assert_eq!(bounds("bytes=6-7, 0-1, 1-2"), [(0, 3), (6, 8)]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_range("bytes=10-", 10), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse_range("bytes=-0", 10), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse_range("items=0-1", 10), Err(RangeError::Invalid));
+        assert_eq!(parse_range("bytes=5-1", 10), Err(RangeError::Invalid));
+        assert_eq!(parse_range("bytes=a-b", 10), Err(RangeError::Invalid));
+    }
+}
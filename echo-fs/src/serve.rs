@@ -0,0 +1,368 @@
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::fs::Metadata;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use echo_core::body::{BodyExt, BoxBody, Bytes, Frame, StreamBody};
+use echo_core::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use echo_core::response::IntoResponse;
+use echo_core::Response;
+use mime::Mime;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::encoding::{Encoding, Precompressed};
+use crate::range::{parse_range, RangeError};
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// [`ServeDir`](crate::ServeDir)和[`ServeFile`](crate::ServeFile)共用的选项。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Options {
+    pub(crate) precompressed: Precompressed,
+    pub(crate) chunk_size: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            precompressed: Default::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+/// 只支持`GET`和`HEAD`。
+pub(crate) fn method_not_allowed(method: &Method) -> Option<Response> {
+    if method == Method::GET || method == Method::HEAD {
+        return None;
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+    Some((StatusCode::METHOD_NOT_ALLOWED, headers).into_response())
+}
+
+pub(crate) fn io_error(error: io::Error) -> Response {
+    match error.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => {
+            StatusCode::NOT_FOUND.into_response()
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// 响应`path`处的文件，`mime`为原文件（而不是预压缩文件）的类型。
+pub(crate) async fn serve_file(
+    method: Method,
+    headers: HeaderMap,
+    path: &Path,
+    mime: Mime,
+    options: Options,
+) -> Response {
+    if let Some(response) = method_not_allowed(&method) {
+        return response;
+    }
+
+    let (file, metadata, encoding) =
+        match open(path, &options.precompressed.accepted(&headers)).await {
+            Ok(opened) => opened,
+            Err(error) => return io_error(error),
+        };
+
+    let len = metadata.len();
+    let modified = metadata.modified().ok().map(truncate_to_secs);
+    let etag = etag(&metadata, encoding);
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Ok(etag) = HeaderValue::try_from(etag.as_str()) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    if let Some(modified) = modified {
+        if let Ok(value) = HeaderValue::try_from(httpdate::fmt_http_date(modified)) {
+            response_headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+    if options.precompressed.is_enabled() {
+        response_headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+
+    if let Some(status) = precondition(&headers, &etag, modified) {
+        return (status, response_headers).into_response();
+    }
+
+    if let Some(encoding) = encoding {
+        response_headers.insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.name()),
+        );
+    }
+
+    let ranges = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| if_range(&headers, &etag, modified))
+        .map(|range| parse_range(range, len));
+
+    let (status, parts) = match ranges {
+        Some(Ok(ranges)) if ranges.len() == 1 => {
+            let range = ranges.into_iter().next().unwrap();
+            if let Ok(value) = HeaderValue::try_from(content_range(&range, len)) {
+                response_headers.insert(header::CONTENT_RANGE, value);
+            }
+            insert_mime(&mut response_headers, &mime);
+            (StatusCode::PARTIAL_CONTENT, vec![Part::File(range)])
+        }
+        Some(Ok(ranges)) => {
+            let boundary = boundary();
+            let content_type = format!("multipart/byteranges; boundary={boundary}");
+            if let Ok(value) = HeaderValue::try_from(content_type) {
+                response_headers.insert(header::CONTENT_TYPE, value);
+            }
+            (
+                StatusCode::PARTIAL_CONTENT,
+                byteranges(ranges, len, &mime, &boundary),
+            )
+        }
+        Some(Err(RangeError::Unsatisfiable)) => {
+            if let Ok(value) = HeaderValue::try_from(format!("bytes */{len}")) {
+                response_headers.insert(header::CONTENT_RANGE, value);
+            }
+            return (StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response();
+        }
+        Some(Err(RangeError::Invalid)) | None => {
+            insert_mime(&mut response_headers, &mime);
+            (StatusCode::OK, vec![Part::File(0..len)])
+        }
+    };
+
+    let content_length = parts.iter().map(Part::len).sum::<u64>();
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content_length));
+
+    let body = if method == Method::HEAD {
+        BoxBody::default()
+    } else {
+        file_body(file, parts, options.chunk_size)
+    };
+
+    let mut response = (status, response_headers).into_response();
+    *response.body_mut() = body;
+    response
+}
+
+/// 依次尝试客户端接受的预压缩文件，都不存在时打开原文件。
+async fn open(
+    path: &Path,
+    encodings: &[Encoding],
+) -> io::Result<(File, Metadata, Option<Encoding>)> {
+    for &encoding in encodings {
+        let mut variant = path.as_os_str().to_owned();
+        variant.push(encoding.extension());
+        if let Ok(file) = File::open(&variant).await {
+            match file.metadata().await {
+                Ok(metadata) if metadata.is_file() => return Ok((file, metadata, Some(encoding))),
+                _ => continue,
+            }
+        }
+    }
+
+    let file = File::open(path).await?;
+    let metadata = file.metadata().await?;
+    if !metadata.is_file() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+    Ok((file, metadata, None))
+}
+
+fn insert_mime(headers: &mut HeaderMap, mime: &Mime) {
+    if let Ok(value) = HeaderValue::try_from(mime.as_ref()) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+}
+
+/// HTTP日期只精确到秒。
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn etag(metadata: &Metadata, encoding: Option<Encoding>) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+    match encoding {
+        Some(encoding) => format!("\"{modified:x}-{:x}-{}\"", metadata.len(), encoding.name()),
+        None => format!("\"{modified:x}-{:x}\"", metadata.len()),
+    }
+}
+
+/// 按RFC 7232第6节的顺序检查条件请求，返回`304`或`412`。
+fn precondition(
+    headers: &HeaderMap,
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> Option<StatusCode> {
+    if let Some(if_match) = header_str(headers, header::IF_MATCH) {
+        if !etag_matches(if_match, etag, false) {
+            return Some(StatusCode::PRECONDITION_FAILED);
+        }
+    } else if let Some(since) = header_date(headers, header::IF_UNMODIFIED_SINCE) {
+        if modified.is_some_and(|modified| modified > since) {
+            return Some(StatusCode::PRECONDITION_FAILED);
+        }
+    }
+
+    if let Some(if_none_match) = header_str(headers, header::IF_NONE_MATCH) {
+        if etag_matches(if_none_match, etag, true) {
+            return Some(StatusCode::NOT_MODIFIED);
+        }
+    } else if let Some(since) = header_date(headers, header::IF_MODIFIED_SINCE) {
+        if modified.is_some_and(|modified| modified <= since) {
+            return Some(StatusCode::NOT_MODIFIED);
+        }
+    }
+
+    None
+}
+
+/// 没有`If-Range`或其与当前文件一致时才处理`Range`。
+fn if_range(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    let Some(value) = header_str(headers, header::IF_RANGE) else {
+        return true;
+    };
+    if value.starts_with('"') || value.starts_with("W/") {
+        !value.starts_with("W/") && value == etag
+    } else {
+        httpdate::parse_http_date(value).is_ok_and(|date| Some(date) == modified)
+    }
+}
+
+fn etag_matches(list: &str, etag: &str, weak: bool) -> bool {
+    list.split(',').map(str::trim).any(|candidate| {
+        if candidate == "*" {
+            return true;
+        }
+        match candidate.strip_prefix("W/") {
+            Some(candidate) => weak && candidate == etag,
+            None => candidate == etag,
+        }
+    })
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn header_date(headers: &HeaderMap, name: header::HeaderName) -> Option<SystemTime> {
+    header_str(headers, name).and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{len}", range.start, range.end - 1)
+}
+
+fn boundary() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos()),
+    );
+    format!("{:016x}", hasher.finish())
+}
+
+fn byteranges(ranges: Vec<Range<u64>>, len: u64, mime: &Mime, boundary: &str) -> Vec<Part> {
+    let mut parts = Vec::with_capacity(ranges.len() * 2 + 1);
+    for (i, range) in ranges.into_iter().enumerate() {
+        let head = format!(
+            "{}--{boundary}\r\nContent-Type: {mime}\r\nContent-Range: {}\r\n\r\n",
+            if i == 0 { "" } else { "\r\n" },
+            content_range(&range, len),
+        );
+        parts.push(Part::Bytes(Bytes::from(head)));
+        parts.push(Part::File(range));
+    }
+    parts.push(Part::Bytes(Bytes::from(format!("\r\n--{boundary}--\r\n"))));
+    parts
+}
+
+enum Part {
+    Bytes(Bytes),
+    File(Range<u64>),
+}
+
+impl Part {
+    fn len(&self) -> u64 {
+        match self {
+            Part::Bytes(bytes) => bytes.len() as u64,
+            Part::File(range) => range.end - range.start,
+        }
+    }
+}
+
+struct FileStream {
+    file: File,
+    position: u64,
+    parts: VecDeque<Part>,
+    chunk_size: usize,
+}
+
+impl FileStream {
+    async fn next(&mut self) -> Option<io::Result<Bytes>> {
+        loop {
+            match self.parts.pop_front()? {
+                Part::Bytes(bytes) => return Some(Ok(bytes)),
+                Part::File(range) if range.is_empty() => continue,
+                Part::File(range) => {
+                    let result = self.read(range.clone()).await;
+                    if let Ok(bytes) = &result {
+                        let start = range.start + bytes.len() as u64;
+                        self.parts.push_front(Part::File(start..range.end));
+                    } else {
+                        self.parts.clear();
+                    }
+                    return Some(result);
+                }
+            }
+        }
+    }
+
+    async fn read(&mut self, range: Range<u64>) -> io::Result<Bytes> {
+        if self.position != range.start {
+            self.file.seek(SeekFrom::Start(range.start)).await?;
+            self.position = range.start;
+        }
+
+        let len = (range.end - range.start).min(self.chunk_size as u64) as usize;
+        let mut buf = vec![0; len];
+        let n = self.file.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.truncate(n);
+        self.position += n as u64;
+        Ok(buf.into())
+    }
+}
+
+fn file_body(file: File, parts: Vec<Part>, chunk_size: usize) -> BoxBody {
+    let stream = FileStream {
+        file,
+        position: 0,
+        parts: parts.into(),
+        chunk_size: chunk_size.max(1),
+    };
+    let stream = futures_util::stream::unfold(stream, |mut stream| async move {
+        let item = stream.next().await?;
+        Some((item.map(Frame::data), stream))
+    });
+    StreamBody::new(stream).boxed()
+}
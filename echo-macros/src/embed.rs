@@ -0,0 +1,187 @@
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Error, Ident, LitStr, Token};
+
+pub fn embed_dir(input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(input as Args);
+    match expand(args) {
+        Ok(stream) => stream.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Args {
+    path: LitStr,
+    gzip: bool,
+    br: bool,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse::<LitStr>()?;
+        let mut args = Args {
+            path,
+            gzip: false,
+            br: false,
+        };
+
+        if input.is_empty() {
+            return Ok(args);
+        }
+        input.parse::<Token![,]>()?;
+
+        for option in Punctuated::<Ident, Token![,]>::parse_terminated(input)? {
+            match option.to_string().as_str() {
+                "gzip" => args.gzip = true,
+                "br" => args.br = true,
+                _ => {
+                    return Err(Error::new_spanned(
+                        option,
+                        "unknown option, expected `gzip` or `br`",
+                    ))
+                }
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn expand(args: Args) -> syn::Result<TokenStream2> {
+    if args.gzip && !cfg!(feature = "embed-gzip") {
+        return Err(Error::new_spanned(
+            &args.path,
+            "`gzip` requires the `embed-gzip` feature",
+        ));
+    }
+    if args.br && !cfg!(feature = "embed-br") {
+        return Err(Error::new_spanned(
+            &args.path,
+            "`br` requires the `embed-br` feature",
+        ));
+    }
+
+    // 相对路径以调用方crate的根目录为基准。
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let root = Path::new(&manifest_dir).join(args.path.value());
+    if !root.is_dir() {
+        return Err(Error::new_spanned(
+            &args.path,
+            format!("`{}` is not a directory", root.display()),
+        ));
+    }
+
+    let mut files = Vec::new();
+    collect(&root, &root, &mut files)
+        .map_err(|e| Error::new_spanned(&args.path, format!("failed to read directory: {e}")))?;
+    files.sort();
+
+    let mut entries = Vec::with_capacity(files.len());
+    for (relative, path) in files {
+        let contents = std::fs::read(&path).map_err(|e| {
+            Error::new_spanned(
+                &args.path,
+                format!("failed to read `{}`: {e}", path.display()),
+            )
+        })?;
+
+        let absolute = path.to_string_lossy();
+        let etag = format!("\"{:016x}-{:x}\"", fnv1a(&contents), contents.len());
+        let content_type = mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .to_string();
+        let gzip = variant(args.gzip.then(|| gzip(&contents)), contents.len());
+        let br = variant(args.br.then(|| br(&contents)), contents.len());
+
+        entries.push(quote! {
+            ::echo::fs::EmbeddedFile::new(
+                #relative,
+                ::std::include_bytes!(#absolute),
+                #gzip,
+                #br,
+                #etag,
+                #content_type,
+            )
+        });
+    }
+
+    let root = root.to_string_lossy();
+    Ok(quote! {
+        ::echo::fs::EmbeddedDir::new(#root, &[#(#entries),*])
+    })
+}
+
+/// 收集目录下的所有文件，返回以`/`分隔的相对路径和绝对路径。
+///
+/// 指向文件的符号链接按文件嵌入，指向目录的符号链接被跳过，避免循环链接导致无限递归。
+fn collect(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect(root, &path, files)?;
+        } else if path.is_file() {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// 只保留比原文件小的压缩结果。
+fn variant(compressed: Option<Vec<u8>>, len: usize) -> TokenStream2 {
+    match compressed.filter(|bytes| bytes.len() < len) {
+        Some(bytes) => {
+            let bytes = Literal::byte_string(&bytes);
+            quote!(::std::option::Option::Some(#bytes))
+        }
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(feature = "embed-gzip")]
+fn gzip(contents: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(contents).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(not(feature = "embed-gzip"))]
+fn gzip(_: &[u8]) -> Vec<u8> {
+    unreachable!()
+}
+
+#[cfg(feature = "embed-br")]
+fn br(contents: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: 11,
+        ..Default::default()
+    };
+    brotli::BrotliCompress(&mut &contents[..], &mut output, &params).unwrap();
+    output
+}
+
+#[cfg(not(feature = "embed-br"))]
+fn br(_: &[u8]) -> Vec<u8> {
+    unreachable!()
+}
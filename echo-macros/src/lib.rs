@@ -1,3 +1,4 @@
+mod embed;
 mod route;
 
 use proc_macro::TokenStream;
@@ -18,3 +19,23 @@ use proc_macro::TokenStream;
 pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
     route::route(args, input)
 }
+
+/// 在编译时将目录嵌入可执行文件，返回`echo::fs::EmbeddedDir`，需要启用`fs`特性。
+///
+/// 路径相对于调用方crate的根目录。可选的`gzip`、`br`会在编译时生成预压缩内容，
+/// 分别需要启用`embed-gzip`、`embed-br`特性。只有嵌入的文件被修改时才会触发重新编译，
+/// 新增或删除文件后需要手动重新编译。
+///
+/// # 例子
+///
+/// ```ignore
+/// use echo::fs::EmbeddedDir;
+///
+/// static ASSETS: EmbeddedDir = echo::embed_dir!("assets", gzip);
+///
+/// let router = echo::route::Router::new().scope("/assets", ASSETS.into_service());
+/// ```
+#[proc_macro]
+pub fn embed_dir(input: TokenStream) -> TokenStream {
+    embed::embed_dir(input)
+}
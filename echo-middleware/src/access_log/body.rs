@@ -0,0 +1,83 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use echo_core::body::{Body, Bytes, Frame, SizeHint};
+use pin_project_lite::pin_project;
+
+use super::{AccessLogEntry, Logger};
+
+/// 等待响应体发送完成的日志记录。
+pub(crate) struct Pending {
+    pub(crate) entry: AccessLogEntry,
+    pub(crate) start: Instant,
+    pub(crate) logger: Logger,
+}
+
+impl Pending {
+    fn finish(mut self) {
+        self.entry.duration = self.start.elapsed();
+        self.logger.log(&self.entry);
+    }
+}
+
+pin_project! {
+    /// 统计已发送的字节数，发送完成、出错或被丢弃时写入日志。
+    pub struct AccessLogBody<B> {
+        #[pin]
+        inner: B,
+        pending: Option<Pending>,
+    }
+
+    impl<B> PinnedDrop for AccessLogBody<B> {
+        fn drop(this: Pin<&mut Self>) {
+            if let Some(pending) = this.project().pending.take() {
+                pending.finish();
+            }
+        }
+    }
+}
+
+impl<B> AccessLogBody<B> {
+    pub(crate) fn new(inner: B, pending: Pending) -> Self {
+        Self {
+            inner,
+            pending: Some(pending),
+        }
+    }
+}
+
+impl<B: Body> Body for AccessLogBody<B> {
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let this = self.project();
+        let frame = std::task::ready!(this.inner.poll_frame(cx));
+        match &frame {
+            Some(Ok(frame)) => {
+                if let (Some(data), Some(pending)) = (frame.data_ref(), this.pending.as_mut()) {
+                    pending.entry.bytes_sent += data.len() as u64;
+                }
+            }
+            Some(Err(_)) | None => {
+                if let Some(pending) = this.pending.take() {
+                    pending.finish();
+                }
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> std::fmt::Debug for AccessLogBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessLogBody").finish()
+    }
+}
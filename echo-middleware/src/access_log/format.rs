@@ -0,0 +1,215 @@
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::AccessLogEntry;
+
+/// 访问日志的格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Common Log Format。
+    Common,
+    /// Combined Log Format，在Common的基础上增加`Referer`和`User-Agent`。
+    #[default]
+    Combined,
+    /// 每行一个JSON对象。
+    Json,
+}
+
+pub(crate) fn format(entry: &AccessLogEntry, format: LogFormat) -> String {
+    let mut line = String::new();
+    match format {
+        LogFormat::Common => common(&mut line, entry),
+        LogFormat::Combined => {
+            common(&mut line, entry);
+            line.push_str(" \"");
+            escape_clf(&mut line, entry.referer().unwrap_or("-"));
+            line.push_str("\" \"");
+            escape_clf(&mut line, entry.user_agent().unwrap_or("-"));
+            line.push('"');
+        }
+        LogFormat::Json => json(&mut line, entry),
+    }
+    line
+}
+
+fn common(line: &mut String, entry: &AccessLogEntry) {
+    match entry.remote_addr() {
+        Some(addr) => write!(line, "{}", addr.ip()).unwrap(),
+        None => line.push('-'),
+    }
+    line.push_str(" - - [");
+    clf_time(line, entry.time());
+    line.push_str("] \"");
+    escape_clf(line, entry.method().as_str());
+    line.push(' ');
+    escape_clf(line, &entry.uri().to_string());
+    write!(
+        line,
+        " {:?}\" {} ",
+        entry.version(),
+        entry.status().as_u16()
+    )
+    .unwrap();
+    match entry.bytes_sent() {
+        0 => line.push('-'),
+        n => write!(line, "{n}").unwrap(),
+    }
+}
+
+fn json(line: &mut String, entry: &AccessLogEntry) {
+    line.push_str("{\"time\":\"");
+    rfc3339_time(line, entry.time());
+    line.push('"');
+
+    let fields = [
+        (
+            "remote_addr",
+            entry.remote_addr().map(|addr| addr.to_string()),
+        ),
+        ("request_id", entry.request_id().map(str::to_owned)),
+        ("method", Some(entry.method().to_string())),
+        ("uri", Some(entry.uri().to_string())),
+        ("matched_path", entry.matched_path().map(str::to_owned)),
+        ("version", Some(format!("{:?}", entry.version()))),
+        ("referer", entry.referer().map(str::to_owned)),
+        ("user_agent", entry.user_agent().map(str::to_owned)),
+    ];
+    for (name, value) in fields {
+        write!(line, ",\"{name}\":").unwrap();
+        match value {
+            Some(value) => escape_json(line, &value),
+            None => line.push_str("null"),
+        }
+    }
+
+    write!(
+        line,
+        ",\"status\":{},\"bytes_sent\":{},\"time_to_first_byte_ms\":{:.3},\"duration_ms\":{:.3}}}",
+        entry.status().as_u16(),
+        entry.bytes_sent(),
+        entry.time_to_first_byte().as_secs_f64() * 1000.0,
+        entry.duration().as_secs_f64() * 1000.0,
+    )
+    .unwrap();
+}
+
+/// 与Apache一致，转义引号、反斜杠和不可见字符。
+fn escape_clf(line: &mut String, value: &str) {
+    for b in value.bytes() {
+        match b {
+            b'"' => line.push_str("\\\""),
+            b'\\' => line.push_str("\\\\"),
+            0x20..=0x7e => line.push(b as char),
+            _ => write!(line, "\\x{b:02x}").unwrap(),
+        }
+    }
+}
+
+fn escape_json(line: &mut String, value: &str) {
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c < '\u{20}' => write!(line, "\\u{:04x}", c as u32).unwrap(),
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// `10/Oct/2000:13:55:36 +0000`
+fn clf_time(line: &mut String, time: SystemTime) {
+    let t = UtcTime::new(time);
+    write!(
+        line,
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        t.day,
+        MONTHS[t.month as usize - 1],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+    .unwrap();
+}
+
+/// `2000-10-10T13:55:36.000Z`
+fn rfc3339_time(line: &mut String, time: SystemTime) {
+    let t = UtcTime::new(time);
+    write!(
+        line,
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis
+    )
+    .unwrap();
+}
+
+struct UtcTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    millis: u32,
+}
+
+impl UtcTime {
+    fn new(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs() as i64;
+        let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400) as u32);
+
+        // 参见 http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+            millis: since_epoch.subsec_millis(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{clf_time, rfc3339_time};
+
+    #[test]
+    fn time() {
+        let time = UNIX_EPOCH + Duration::from_millis(971_186_136_123);
+        let mut line = String::new();
+        clf_time(&mut line, time);
+        assert_eq!(line, "10/Oct/2000:13:55:36 +0000");
+
+        let mut line = String::new();
+        rfc3339_time(&mut line, time);
+        assert_eq!(line, "2000-10-10T13:55:36.123Z");
+
+        let mut line = String::new();
+        rfc3339_time(&mut line, UNIX_EPOCH + Duration::from_secs(951_782_400));
+        assert_eq!(line, "2000-02-29T00:00:00.000Z");
+    }
+}
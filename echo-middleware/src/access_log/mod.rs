@@ -0,0 +1,525 @@
+mod body;
+mod format;
+
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+use echo_core::body::{Body, BodyExt, LengthLimitError};
+use echo_core::http::{header, HeaderMap, Method, StatusCode, Uri, Version};
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, RemoteAddr, Request, Response};
+use echo_route::{MatchedPath, RouteError, RouteErrorKind};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+pub use self::body::AccessLogBody;
+pub use self::format::LogFormat;
+
+use self::body::Pending;
+
+/// 访问日志。
+///
+/// 响应体发送完成后才写入日志，此时才能得到实际发送的字节数和总耗时。
+/// 应当放在最外层，匹配的路由模板从响应扩展中的[`MatchedPath`]读取，
+/// 请求ID优先读取`x-request-id`响应头，其次是请求中的[`RequestId`]和`x-request-id`请求头，
+/// 内部服务出错时从[`RequestIdError`]中读取，因此可以放在`RequestIdMiddleware`外层。
+///
+/// 放在`CatchErrorMiddleware`外层时，内部服务返回的错误按类型推断状态码：
+/// 路由错误为404、405、406、415或400，[`RateLimitError`]为429，[`TimeoutError`]为504，
+/// 请求体超过限制为413，其他错误为500。
+///
+/// [`RequestId`]: crate::request_id::RequestId
+/// [`RequestIdError`]: crate::request_id::RequestIdError
+/// [`RateLimitError`]: crate::rate_limit::RateLimitError
+/// [`TimeoutError`]: crate::timeout::TimeoutError
+#[derive(Debug, Clone)]
+pub struct AccessLogMiddleware {
+    logger: Logger,
+}
+
+impl Default for AccessLogMiddleware {
+    fn default() -> Self {
+        Self::writer(std::io::stdout())
+    }
+}
+
+impl AccessLogMiddleware {
+    /// 写入标准输出。
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 每条日志写入一行。
+    pub fn writer<W>(writer: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        Self::with_sink(Sink::Writer(Mutex::new(Box::new(writer))))
+    }
+
+    /// 将格式化后的日志发送到通道，接收端关闭后丢弃日志。
+    pub fn channel(sender: mpsc::Sender<String>) -> Self {
+        Self::with_sink(Sink::Channel(sender))
+    }
+
+    /// 由回调处理日志，可以通过[`AccessLogEntry::format`]格式化。
+    pub fn callback<F>(f: F) -> Self
+    where
+        F: Fn(&AccessLogEntry) + Send + Sync + 'static,
+    {
+        Self::with_sink(Sink::Callback(Box::new(f)))
+    }
+
+    /// 日志格式，默认为[`LogFormat::Combined`]，对回调无效。
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.logger.format = format;
+        self
+    }
+
+    fn with_sink(sink: Sink) -> Self {
+        Self {
+            logger: Logger {
+                sink: Arc::new(sink),
+                format: LogFormat::default(),
+            },
+        }
+    }
+}
+
+impl<S> Middleware<S> for AccessLogMiddleware {
+    type Service = AccessLog<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        AccessLog {
+            inner: service,
+            logger: self.logger,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AccessLog<S> {
+    inner: S,
+    logger: Logger,
+}
+
+impl<S, B, ResB> Service<Request<B>> for AccessLog<S>
+where
+    S: Service<Request<B>, Response = echo_core::http::Response<ResB>>,
+    S::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = BoxError;
+    type Future = AccessLogFuture<S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let start = Instant::now();
+        let headers = request.headers();
+        let entry = AccessLogEntry {
+            time: SystemTime::now(),
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            version: request.version(),
+            status: StatusCode::OK,
+            bytes_sent: 0,
+            time_to_first_byte: Duration::ZERO,
+            duration: Duration::ZERO,
+            remote_addr: request.extensions().get::<RemoteAddr>().map(|addr| addr.0),
+            request_id: request_id(&request),
+            matched_path: None,
+            referer: header_str(headers, header::REFERER),
+            user_agent: header_str(headers, header::USER_AGENT),
+        };
+
+        AccessLogFuture {
+            fut: self.inner.call(request),
+            pending: Some(Pending {
+                entry,
+                start,
+                logger: self.logger.clone(),
+            }),
+        }
+    }
+}
+
+impl<S> fmt::Debug for AccessLog<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLog")
+            .field("inner", &self.inner)
+            .field("logger", &self.logger)
+            .finish()
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+}
+
+const X_REQUEST_ID: header::HeaderName = header::HeaderName::from_static("x-request-id");
+
+fn request_id<B>(request: &Request<B>) -> Option<String> {
+    #[cfg(feature = "request-id")]
+    if let Some(id) = request.extensions().get::<crate::request_id::RequestId>() {
+        return Some(id.as_str().to_owned());
+    }
+    header_str(request.headers(), X_REQUEST_ID)
+}
+
+/// 外层的错误处理程序通常会将这些错误转换为对应的响应，沿着`source`查找第一个可以识别的错误。
+fn error_status(err: &(dyn std::error::Error + 'static)) -> StatusCode {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        if let Some(err) = err.downcast_ref::<RouteError>() {
+            return match err.kind() {
+                RouteErrorKind::NotFound => StatusCode::NOT_FOUND,
+                RouteErrorKind::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+                RouteErrorKind::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+                RouteErrorKind::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                RouteErrorKind::InvalidEncoding => StatusCode::BAD_REQUEST,
+            };
+        }
+        #[cfg(feature = "rate-limit")]
+        if err.is::<crate::rate_limit::RateLimitError>() {
+            return StatusCode::TOO_MANY_REQUESTS;
+        }
+        #[cfg(feature = "timeout")]
+        if err.is::<crate::timeout::TimeoutError>() {
+            return StatusCode::GATEWAY_TIMEOUT;
+        }
+        if err.is::<LengthLimitError>() {
+            return StatusCode::PAYLOAD_TOO_LARGE;
+        }
+        next = err.source();
+    }
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+pin_project! {
+    pub struct AccessLogFuture<F> {
+        #[pin]
+        fut: F,
+        pending: Option<Pending>,
+    }
+}
+
+impl<F, B, E> Future for AccessLogFuture<F>
+where
+    F: Future<Output = Result<echo_core::http::Response<B>, E>>,
+    E: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = ready!(this.fut.poll(cx));
+        let mut pending = this.pending.take().expect("polled after completion");
+        pending.entry.time_to_first_byte = pending.start.elapsed();
+
+        match result {
+            Ok(response) => {
+                pending.entry.status = response.status();
+                if let Some(id) = header_str(response.headers(), X_REQUEST_ID) {
+                    pending.entry.request_id = Some(id);
+                }
+                pending.entry.matched_path = response
+                    .extensions()
+                    .get::<MatchedPath>()
+                    .map(|path| path.as_str().to_owned());
+                let response = response.map(|body| AccessLogBody::new(body, pending).boxed());
+                Poll::Ready(Ok(response))
+            }
+            Err(err) => {
+                let err = err.into();
+                pending.entry.status = error_status(&*err);
+                #[cfg(feature = "request-id")]
+                if let Some(id) = crate::request_id::RequestIdError::find(&*err) {
+                    pending.entry.request_id = Some(id.as_str().to_owned());
+                }
+                pending.entry.duration = pending.entry.time_to_first_byte;
+                pending.logger.log(&pending.entry);
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+}
+
+impl<F> fmt::Debug for AccessLogFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLogFuture").finish()
+    }
+}
+
+/// 一条访问日志。
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    time: SystemTime,
+    method: Method,
+    uri: Uri,
+    version: Version,
+    status: StatusCode,
+    bytes_sent: u64,
+    time_to_first_byte: Duration,
+    duration: Duration,
+    remote_addr: Option<SocketAddr>,
+    request_id: Option<String>,
+    matched_path: Option<String>,
+    referer: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl AccessLogEntry {
+    /// 收到请求的时间。
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// 实际发送的响应体字节数。
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// 从收到请求到内部服务返回响应的时间。
+    pub fn time_to_first_byte(&self) -> Duration {
+        self.time_to_first_byte
+    }
+
+    /// 从收到请求到响应体发送完成的时间。
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// 匹配的路由模板。
+    pub fn matched_path(&self) -> Option<&str> {
+        self.matched_path.as_deref()
+    }
+
+    pub fn referer(&self) -> Option<&str> {
+        self.referer.as_deref()
+    }
+
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    pub fn format(&self, format: LogFormat) -> String {
+        format::format(self, format)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Logger {
+    sink: Arc<Sink>,
+    format: LogFormat,
+}
+
+enum Sink {
+    Writer(Mutex<Box<dyn Write + Send>>),
+    Channel(mpsc::Sender<String>),
+    Callback(Box<dyn Fn(&AccessLogEntry) + Send + Sync>),
+}
+
+impl Logger {
+    fn log(&self, entry: &AccessLogEntry) {
+        match &*self.sink {
+            Sink::Writer(writer) => {
+                let mut line = entry.format(self.format);
+                line.push('\n');
+                if let Ok(mut writer) = writer.lock() {
+                    let _ = writer.write_all(line.as_bytes());
+                }
+            }
+            Sink::Channel(sender) => {
+                let _ = sender.send(entry.format(self.format));
+            }
+            Sink::Callback(f) => f(entry),
+        }
+    }
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sink = match &*self.sink {
+            Sink::Writer(_) => "Writer",
+            Sink::Channel(_) => "Channel",
+            Sink::Callback(_) => "Callback",
+        };
+        f.debug_struct("Logger")
+            .field("sink", &sink)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::StatusCode;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{RemoteAddr, Request};
+    use echo_route::{get, Router};
+
+    use super::{AccessLogMiddleware, LogFormat};
+
+    fn request() -> Request {
+        let mut request = Request::builder()
+            .uri("/users/1?q=1")
+            .header("user-agent", "curl \"8\"")
+            .header("x-request-id", "abc")
+            .body(Default::default())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(RemoteAddr("127.0.0.1:8080".parse().unwrap()));
+        request
+    }
+
+    fn router() -> Router {
+        Router::new().route(
+            "/users/:id",
+            get(service_fn(|_: Request| async {
+                Ok::<_, Infallible>("hello")
+            })),
+        )
+    }
+
+    #[tokio::test]
+    async fn combined() {
+        let (tx, rx) = mpsc::channel();
+        let service = router().with(AccessLogMiddleware::channel(tx));
+
+        let response = service.call(request()).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        response.into_body().collect().bytes().await.unwrap();
+
+        let line = rx.try_recv().unwrap();
+        assert!(line.starts_with("127.0.0.1 - - ["), "{line}");
+        assert!(
+            line.ends_with("] \"GET /users/1?q=1 HTTP/1.1\" 200 5 \"-\" \"curl \\\"8\\\"\""),
+            "{line}"
+        );
+    }
+
+    #[tokio::test]
+    async fn json() {
+        let (tx, rx) = mpsc::channel();
+        let service = router().with(AccessLogMiddleware::channel(tx).format(LogFormat::Json));
+
+        let response = service.call(request()).await.unwrap();
+        drop(response);
+
+        let line = rx.try_recv().unwrap();
+        for field in [
+            "\"remote_addr\":\"127.0.0.1:8080\"",
+            "\"request_id\":\"abc\"",
+            "\"matched_path\":\"/users/:id\"",
+            "\"referer\":null",
+            "\"status\":200",
+            "\"bytes_sent\":0",
+        ] {
+            assert!(line.contains(field), "{line}");
+        }
+    }
+
+    #[tokio::test]
+    async fn error_status() {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let logged = entries.clone();
+        let service = router().with(AccessLogMiddleware::callback(move |entry| {
+            logged.lock().unwrap().push(entry.status());
+        }));
+
+        let request: Request = Request::builder()
+            .uri("/missing")
+            .body(Default::default())
+            .unwrap();
+        assert!(service.call(request).await.is_err());
+
+        let request: Request = Request::builder()
+            .method("POST")
+            .uri("/users/1")
+            .body(Default::default())
+            .unwrap();
+        assert!(service.call(request).await.is_err());
+
+        assert_eq!(
+            *entries.lock().unwrap(),
+            [StatusCode::NOT_FOUND, StatusCode::METHOD_NOT_ALLOWED]
+        );
+    }
+
+    #[cfg(feature = "request-id")]
+    #[tokio::test]
+    async fn generated_request_id() {
+        let (tx, rx) = mpsc::channel();
+        let service = router()
+            .with(crate::request_id::RequestIdMiddleware::new())
+            .with(AccessLogMiddleware::channel(tx).format(LogFormat::Json));
+
+        let request: Request = Request::builder()
+            .uri("/users/1")
+            .body(Default::default())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        let id = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        drop(response);
+
+        let line = rx.try_recv().unwrap();
+        assert!(line.contains(&format!("\"request_id\":\"{id}\"")), "{line}");
+
+        let request: Request = Request::builder()
+            .uri("/missing")
+            .body(Default::default())
+            .unwrap();
+        let err = service.call(request).await.unwrap_err();
+        let id = crate::request_id::RequestIdError::find(&*err).unwrap();
+        let line = rx.try_recv().unwrap();
+        assert!(line.contains(&format!("\"request_id\":\"{id}\"")), "{line}");
+        assert!(line.contains("\"status\":404"), "{line}");
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::http::{header, HeaderMap, StatusCode};
+use echo_core::response::IntoResponse;
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+/// 限制请求体的大小。
+///
+/// `Content-Length`超过限制时直接返回413，否则将请求体包装为[`Limited`]，
+/// 读取超过限制时返回[`LengthLimitError`]，可以在错误处理程序中向下转型判断。
+/// 嵌套使用时较小的限制生效。
+///
+/// [`Limited`]: echo_core::body::Limited
+/// [`LengthLimitError`]: echo_core::body::LengthLimitError
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimitMiddleware {
+    limit: usize,
+}
+
+impl BodyLimitMiddleware {
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Middleware<S> for BodyLimitMiddleware {
+    type Service = BodyLimit<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        BodyLimit {
+            inner: service,
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BodyLimit<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S, B, ResB> Service<Request<B>> for BodyLimit<S>
+where
+    S: Service<Request, Response = echo_core::http::Response<ResB>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BodyLimitFuture<S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        if content_length(request.headers()).is_some_and(|len| len > self.limit as u64) {
+            return BodyLimitFuture::PayloadTooLarge;
+        }
+
+        let request = request.map(|body| body.limit(self.limit).boxed());
+        BodyLimitFuture::Inner {
+            fut: self.inner.call(request),
+        }
+    }
+}
+
+impl<S> fmt::Debug for BodyLimit<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyLimit")
+            .field("inner", &self.inner)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+pin_project! {
+    #[project = BodyLimitFutureProj]
+    pub enum BodyLimitFuture<F> {
+        Inner {
+            #[pin]
+            fut: F,
+        },
+        PayloadTooLarge,
+    }
+}
+
+impl<F, B, E> Future for BodyLimitFuture<F>
+where
+    F: Future<Output = Result<echo_core::http::Response<B>, E>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            BodyLimitFutureProj::Inner { fut } => {
+                let response = ready!(fut.poll(cx))?;
+                Poll::Ready(Ok(response.map(BodyExt::boxed)))
+            }
+            BodyLimitFutureProj::PayloadTooLarge => {
+                Poll::Ready(Ok(StatusCode::PAYLOAD_TOO_LARGE.into_response()))
+            }
+        }
+    }
+}
+
+impl<F> fmt::Debug for BodyLimitFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyLimitFuture::Inner { .. } => f.write_str("BodyLimitFuture::Inner"),
+            BodyLimitFuture::PayloadTooLarge => f.write_str("BodyLimitFuture::PayloadTooLarge"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::body::{BodyExt, LengthLimitError};
+    use echo_core::http::StatusCode;
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{BoxError, Request};
+
+    use super::BodyLimitMiddleware;
+
+    #[tokio::test]
+    async fn limit() {
+        let service = service_fn(|request: Request| async move {
+            match request.into_body().collect().bytes().await {
+                Ok(bytes) => Ok::<_, BoxError>(bytes.into_response()),
+                Err(e) => {
+                    assert!(e.is::<LengthLimitError>());
+                    Ok(StatusCode::BAD_REQUEST.into_response())
+                }
+            }
+        })
+        .with(BodyLimitMiddleware::new(4));
+
+        let request = Request::builder()
+            .header("content-length", "5")
+            .body("hello")
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // 没有`Content-Length`时在读取请求体时检查。
+        let response = service.call(Request::new("hello")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = service.call(Request::new("hell")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,317 @@
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, Bytes, Frame, LengthLimitError};
+use echo_core::BoxError;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use super::encoding::Encoding;
+
+/// 解压输出，单次解压的数据超过`limit`时返回[`LengthLimitError`]，避免一次分配过多内存。
+struct Sink {
+    buf: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for Sink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.limit {
+            return Err(io::Error::other(LengthLimitError));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `zstd::stream::write::Decoder`结束时不检查最后一帧是否完整，这里直接使用底层的解压器。
+#[cfg(feature = "compression-zstd")]
+struct ZstdDecoder {
+    raw: zstd::stream::raw::Decoder<'static>,
+    sink: Sink,
+    /// 最近一次解压返回的提示，为0表示当前帧已经结束并且输出已全部取出。
+    hint: usize,
+}
+
+#[cfg(feature = "compression-zstd")]
+impl ZstdDecoder {
+    fn new(sink: Sink) -> Self {
+        Self {
+            raw: zstd::stream::raw::Decoder::new().expect("failed to create zstd decoder"),
+            sink,
+            hint: 0,
+        }
+    }
+
+    fn run(&mut self, data: &[u8]) -> io::Result<()> {
+        use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+
+        let mut input = InBuffer::around(data);
+        let mut buf = [0; 8192];
+        loop {
+            let mut output = OutBuffer::around(&mut buf[..]);
+            self.hint = self.raw.run(&mut input, &mut output)?;
+            let (written, full) = (output.pos(), output.pos() == output.capacity());
+            self.sink.write_all(&buf[..written])?;
+            if input.pos() == data.len() && (!full || self.hint == 0) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.hint != 0 {
+            return Err(invalid_data("incomplete zstd frame"));
+        }
+        Ok(())
+    }
+}
+
+/// `flate2::write::ZlibDecoder`结束时不检查流是否完整，缺少校验和也能通过。
+#[cfg(feature = "compression-deflate")]
+struct ZlibDecoder {
+    raw: flate2::Decompress,
+    sink: Sink,
+    done: bool,
+}
+
+#[cfg(feature = "compression-deflate")]
+impl ZlibDecoder {
+    fn new(sink: Sink) -> Self {
+        Self {
+            raw: flate2::Decompress::new(true),
+            sink,
+            done: false,
+        }
+    }
+
+    fn run(&mut self, mut data: &[u8]) -> io::Result<()> {
+        if self.done {
+            return check_trailing(data);
+        }
+        let mut buf = [0; 8192];
+        loop {
+            let (total_in, total_out) = (self.raw.total_in(), self.raw.total_out());
+            let status = self
+                .raw
+                .decompress(data, &mut buf, flate2::FlushDecompress::None)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let consumed = (self.raw.total_in() - total_in) as usize;
+            let produced = (self.raw.total_out() - total_out) as usize;
+            data = &data[consumed..];
+            self.sink.write_all(&buf[..produced])?;
+
+            if status == flate2::Status::StreamEnd {
+                self.done = true;
+                return check_trailing(data);
+            }
+            if (data.is_empty() && produced < buf.len()) || (consumed == 0 && produced == 0) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.done {
+            return Err(invalid_data("incomplete deflate stream"));
+        }
+        Ok(())
+    }
+}
+
+enum Decoder {
+    #[cfg(feature = "compression-br")]
+    Brotli(Box<brotli::DecompressorWriter<Sink>>),
+    #[cfg(feature = "compression-zstd")]
+    Zstd(ZstdDecoder),
+    #[cfg(feature = "compression-gzip")]
+    Gzip(flate2::write::GzDecoder<Sink>),
+    #[cfg(feature = "compression-deflate")]
+    Deflate(ZlibDecoder),
+}
+
+impl Decoder {
+    fn new(encoding: Encoding, limit: usize) -> Self {
+        let sink = Sink {
+            buf: Vec::new(),
+            limit,
+        };
+        match encoding {
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => {
+                Decoder::Brotli(Box::new(brotli::DecompressorWriter::new(sink, 4096)))
+            }
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => Decoder::Zstd(ZstdDecoder::new(sink)),
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => Decoder::Gzip(flate2::write::GzDecoder::new(sink)),
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => Decoder::Deflate(ZlibDecoder::new(sink)),
+        }
+    }
+
+    fn decode(&mut self, data: &[u8]) -> io::Result<Bytes> {
+        let sink = match self {
+            #[cfg(feature = "compression-br")]
+            Decoder::Brotli(decoder) => {
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "compression-zstd")]
+            Decoder::Zstd(decoder) => {
+                decoder.run(data)?;
+                &mut decoder.sink
+            }
+            #[cfg(feature = "compression-gzip")]
+            Decoder::Gzip(decoder) => {
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "compression-deflate")]
+            Decoder::Deflate(decoder) => {
+                decoder.run(data)?;
+                &mut decoder.sink
+            }
+        };
+        Ok(std::mem::take(&mut sink.buf).into())
+    }
+
+    fn finish(&mut self) -> io::Result<Bytes> {
+        let sink = match self {
+            #[cfg(feature = "compression-br")]
+            Decoder::Brotli(decoder) => {
+                decoder.close()?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "compression-zstd")]
+            Decoder::Zstd(decoder) => {
+                decoder.finish()?;
+                &mut decoder.sink
+            }
+            #[cfg(feature = "compression-gzip")]
+            Decoder::Gzip(decoder) => {
+                decoder.try_finish()?;
+                decoder.get_mut()
+            }
+            #[cfg(feature = "compression-deflate")]
+            Decoder::Deflate(decoder) => {
+                decoder.finish()?;
+                &mut decoder.sink
+            }
+        };
+        Ok(std::mem::take(&mut sink.buf).into())
+    }
+}
+
+enum State {
+    Decoding(Decoder),
+    Trailers(Option<Frame<Bytes>>),
+    Done,
+}
+
+pin_project! {
+    /// 解压后的请求体。
+    pub struct DecompressionBody<B> {
+        #[pin]
+        inner: B,
+        state: State,
+    }
+}
+
+impl<B> DecompressionBody<B> {
+    /// 每个数据帧解压后的大小不能超过`limit`，总大小的限制由外层的`Limited`负责。
+    pub(crate) fn new(inner: B, encoding: Encoding, limit: usize) -> Self {
+        Self {
+            inner,
+            state: State::Decoding(Decoder::new(encoding, limit)),
+        }
+    }
+}
+
+impl<B> Body for DecompressionBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        loop {
+            let this = self.as_mut().project();
+            let decoder = match this.state {
+                State::Decoding(decoder) => decoder,
+                State::Trailers(trailers) => {
+                    let trailers = trailers.take();
+                    *this.state = State::Done;
+                    return Poll::Ready(trailers.map(Ok));
+                }
+                State::Done => return Poll::Ready(None),
+            };
+
+            let (output, next) = match ready!(this.inner.poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => (decoder.decode(&data), None),
+                    Err(trailers) => (decoder.finish(), Some(State::Trailers(Some(trailers)))),
+                },
+                Some(Err(err)) => {
+                    *this.state = State::Done;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                None => (decoder.finish(), Some(State::Done)),
+            };
+            if let Some(next) = next {
+                *this.state = next;
+            }
+
+            match output {
+                Ok(output) if output.is_empty() => continue,
+                Ok(output) => return Poll::Ready(Some(Ok(Frame::data(output)))),
+                Err(err) => {
+                    *this.state = State::Done;
+                    return Poll::Ready(Some(Err(into_box_error(err))));
+                }
+            }
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for DecompressionBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecompressionBody").finish()
+    }
+}
+
+/// 流结束之后不能再有数据。
+#[cfg(feature = "compression-deflate")]
+fn check_trailing(data: &[u8]) -> io::Result<()> {
+    if !data.is_empty() {
+        return Err(invalid_data("trailing data after deflate stream"));
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "compression-deflate", feature = "compression-zstd"))]
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 超出限制时返回[`LengthLimitError`]本身，与`Limited`保持一致。
+fn into_box_error(err: io::Error) -> BoxError {
+    if err
+        .get_ref()
+        .is_some_and(|inner| inner.is::<LengthLimitError>())
+    {
+        return Box::new(LengthLimitError);
+    }
+    err.into()
+}
@@ -0,0 +1,353 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, BodyExt, Limited};
+use echo_core::http::{header, HeaderValue, Response, StatusCode};
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request};
+use pin_project_lite::pin_project;
+
+use super::decode::DecompressionBody;
+use super::encoding::{Encoding, Encodings};
+
+/// 请求体解压，根据`Content-Encoding`解码后再交给内部服务，
+/// 因此`extract::json`、`extract::form`等提取器可以直接读取解压后的内容。
+///
+/// 解压后的大小超过`limit`时读取请求体会返回[`LengthLimitError`]，
+/// 不支持的编码直接返回415，并通过`Accept-Encoding`告知支持的编码。
+///
+/// [`LengthLimitError`]: echo_core::body::LengthLimitError
+#[derive(Debug, Clone, Copy)]
+pub struct DecompressionMiddleware {
+    encodings: Encodings,
+    limit: usize,
+}
+
+impl Default for DecompressionMiddleware {
+    fn default() -> Self {
+        Self {
+            encodings: Encodings::default(),
+            limit: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl DecompressionMiddleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[cfg(feature = "compression-gzip")]
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.encodings.gzip = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-deflate")]
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.encodings.deflate = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-br")]
+    pub fn br(mut self, enable: bool) -> Self {
+        self.encodings.br = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-zstd")]
+    pub fn zstd(mut self, enable: bool) -> Self {
+        self.encodings.zstd = enable;
+        self
+    }
+
+    /// 解压后请求体的最大字节数，默认为2MiB。
+    pub fn limit(mut self, bytes: usize) -> Self {
+        self.limit = bytes;
+        self
+    }
+}
+
+impl<S> Middleware<S> for DecompressionMiddleware {
+    type Service = Decompression<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        Decompression {
+            inner: service,
+            config: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Decompression<S> {
+    inner: S,
+    config: DecompressionMiddleware,
+}
+
+impl<S, B, ResB> Service<Request<B>> for Decompression<S>
+where
+    S: Service<Request, Response = Response<ResB>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = DecompressionFuture<S::Future, ResB>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let (mut parts, body) = request.into_parts();
+
+        let encoding = match content_encoding(&parts.headers, &self.config.encodings) {
+            Ok(encoding) => encoding,
+            Err(()) => {
+                let mut response = Response::new(ResB::default());
+                *response.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+                if let Some(accept) = accept_encoding(&self.config.encodings) {
+                    response
+                        .headers_mut()
+                        .insert(header::ACCEPT_ENCODING, accept);
+                }
+                return DecompressionFuture::Unsupported {
+                    response: Some(response),
+                };
+            }
+        };
+
+        let body = match encoding {
+            Some(encoding) => {
+                parts.headers.remove(header::CONTENT_ENCODING);
+                parts.headers.remove(header::CONTENT_LENGTH);
+                let limit = self.config.limit;
+                Limited::new(DecompressionBody::new(body, encoding, limit), limit).boxed()
+            }
+            None => body.boxed(),
+        };
+
+        DecompressionFuture::Inner {
+            fut: self.inner.call(Request::from_parts(parts, body)),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Decompression<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decompression")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// 返回需要解压的编码，没有编码时返回`None`，不支持的编码或多重编码返回错误。
+fn content_encoding(
+    headers: &header::HeaderMap,
+    encodings: &Encodings,
+) -> Result<Option<Encoding>, ()> {
+    let mut codings = Vec::new();
+    for value in headers.get_all(header::CONTENT_ENCODING) {
+        let value = value.to_str().map_err(|_| ())?;
+        codings.extend(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty() && !c.eq_ignore_ascii_case("identity")),
+        );
+    }
+    match codings[..] {
+        [] => Ok(None),
+        [coding] => Encoding::from_name(coding)
+            .filter(|encoding| encodings.is_enabled(*encoding))
+            .map(Some)
+            .ok_or(()),
+        _ => Err(()),
+    }
+}
+
+fn accept_encoding(encodings: &Encodings) -> Option<HeaderValue> {
+    let names = Encoding::ALL
+        .iter()
+        .filter(|encoding| encodings.is_enabled(**encoding))
+        .map(|encoding| encoding.name())
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        return None;
+    }
+    HeaderValue::try_from(names.join(", ")).ok()
+}
+
+pin_project! {
+    #[project = DecompressionFutureProj]
+    pub enum DecompressionFuture<F, B> {
+        Inner {
+            #[pin]
+            fut: F,
+        },
+        Unsupported {
+            response: Option<Response<B>>,
+        },
+    }
+}
+
+impl<F, B, E> Future for DecompressionFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            DecompressionFutureProj::Inner { fut } => fut.poll(cx),
+            DecompressionFutureProj::Unsupported { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+impl<F, B> fmt::Debug for DecompressionFuture<F, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecompressionFuture::Inner { .. } => f.write_str("DecompressionFuture::Inner"),
+            DecompressionFuture::Unsupported { .. } => {
+                f.write_str("DecompressionFuture::Unsupported")
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compression-gzip"))]
+mod tests {
+    use std::convert::Infallible;
+    use std::io::Write;
+
+    use echo_core::body::{BodyExt, LengthLimitError};
+    use echo_core::http::{header, StatusCode};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use super::DecompressionMiddleware;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn request(content_encoding: &str, body: Vec<u8>) -> Request {
+        Request::builder()
+            .uri("/")
+            .header(header::CONTENT_ENCODING, content_encoding)
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(body.into_response().into_body())
+            .unwrap()
+    }
+
+    async fn echo(request: Request) -> Result<Response, Infallible> {
+        let response = match request.into_body().collect().bytes().await {
+            Ok(body) => body.into_response(),
+            Err(err) if err.is::<LengthLimitError>() => {
+                StatusCode::PAYLOAD_TOO_LARGE.into_response()
+            }
+            Err(_) => StatusCode::BAD_REQUEST.into_response(),
+        };
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn decompress() {
+        let service = service_fn(echo).with(DecompressionMiddleware::new().limit(1024));
+
+        let response = service
+            .call(request("gzip", gzip(b"{\"a\":1}")))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "{\"a\":1}");
+
+        let response = service
+            .call(request("gzip", gzip(&[b'a'; 4096])))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = service
+            .call(request("gzip", b"not gzip".to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(all(
+        feature = "compression-deflate",
+        feature = "compression-br",
+        feature = "compression-zstd"
+    ))]
+    #[tokio::test]
+    async fn truncated() {
+        let data = b"hello world ".repeat(100);
+        let deflate = {
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+            encoder.write_all(&data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let br = {
+            let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+            encoder.write_all(&data).unwrap();
+            encoder.into_inner()
+        };
+        let zstd = zstd::encode_all(&data[..], 0).unwrap();
+
+        let service = service_fn(echo).with(DecompressionMiddleware::new());
+
+        for (encoding, encoded) in [
+            ("gzip", gzip(&data)),
+            ("deflate", deflate),
+            ("br", br),
+            ("zstd", zstd),
+        ] {
+            let response = service
+                .call(request(encoding, encoded.clone()))
+                .await
+                .unwrap();
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, data, "{encoding}");
+
+            // 缺少结尾的几个字节，数据可能已经全部解压出来，但流并不完整。
+            let truncated = encoded[..encoded.len() - 4].to_vec();
+            let response = service.call(request(encoding, truncated)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{encoding}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unsupported() {
+        let service = service_fn(echo).with(DecompressionMiddleware::new());
+
+        for encoding in ["compress", "gzip, gzip"] {
+            let response = service
+                .call(request(encoding, b"data".to_vec()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert!(response.headers()[header::ACCEPT_ENCODING]
+                .to_str()
+                .unwrap()
+                .contains("gzip"));
+        }
+
+        let response = service
+            .call(request("identity", b"data".to_vec()))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "data");
+    }
+}
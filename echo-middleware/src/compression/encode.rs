@@ -0,0 +1,180 @@
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, Bytes, Frame};
+use echo_core::BoxError;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use super::encoding::Encoding;
+
+/// 压缩算法的流式编码器，每次写入后立即刷新，使每个数据帧都能及时发送。
+enum Encoder {
+    #[cfg(feature = "compression-br")]
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    #[cfg(feature = "compression-zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    #[cfg(feature = "compression-gzip")]
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    #[cfg(feature = "compression-deflate")]
+    Deflate(flate2::write::ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> Self {
+        match encoding {
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                4096,
+                4,
+                22,
+            ))),
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => Encoder::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), 3).expect("valid zstd level"),
+            ),
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => Encoder::Deflate(flate2::write::ZlibEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+        }
+    }
+
+    fn encode(&mut self, data: &[u8]) -> io::Result<Bytes> {
+        let output = match self {
+            #[cfg(feature = "compression-br")]
+            Encoder::Brotli(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            #[cfg(feature = "compression-zstd")]
+            Encoder::Zstd(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            #[cfg(feature = "compression-gzip")]
+            Encoder::Gzip(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            #[cfg(feature = "compression-deflate")]
+            Encoder::Deflate(encoder) => {
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output).into())
+    }
+
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            #[cfg(feature = "compression-br")]
+            Encoder::Brotli(encoder) => encoder.into_inner(),
+            #[cfg(feature = "compression-zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?,
+            #[cfg(feature = "compression-gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "compression-deflate")]
+            Encoder::Deflate(encoder) => encoder.finish()?,
+        };
+        Ok(output.into())
+    }
+}
+
+enum State {
+    Encoding(Encoder),
+    /// 压缩数据已结束，等待发送原响应的尾部。
+    Trailers(Option<Frame<Bytes>>),
+    Done,
+}
+
+pin_project! {
+    /// 压缩后的响应体。
+    pub struct CompressionBody<B> {
+        #[pin]
+        inner: B,
+        state: State,
+    }
+}
+
+impl<B> CompressionBody<B> {
+    pub(crate) fn new(inner: B, encoding: Encoding) -> Self {
+        Self {
+            inner,
+            state: State::Encoding(Encoder::new(encoding)),
+        }
+    }
+}
+
+impl<B> Body for CompressionBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        loop {
+            let this = self.as_mut().project();
+            let encoder = match this.state {
+                State::Encoding(encoder) => encoder,
+                State::Trailers(trailers) => {
+                    let trailers = trailers.take();
+                    *this.state = State::Done;
+                    return Poll::Ready(trailers.map(Ok));
+                }
+                State::Done => return Poll::Ready(None),
+            };
+
+            let (output, next) = match ready!(this.inner.poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => (encoder.encode(&data), None),
+                    Err(trailers) => (Ok(Bytes::new()), Some(State::Trailers(Some(trailers)))),
+                },
+                Some(Err(err)) => {
+                    *this.state = State::Done;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                None => (Ok(Bytes::new()), Some(State::Done)),
+            };
+
+            let output = match next {
+                Some(next) => match std::mem::replace(this.state, next) {
+                    State::Encoding(encoder) => encoder.finish(),
+                    _ => unreachable!(),
+                },
+                None => output,
+            };
+
+            match output {
+                Ok(output) if output.is_empty() => continue,
+                Ok(output) => return Poll::Ready(Some(Ok(Frame::data(output)))),
+                Err(err) => {
+                    *this.state = State::Done;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+            }
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for CompressionBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressionBody").finish()
+    }
+}
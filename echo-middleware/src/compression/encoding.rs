@@ -0,0 +1,153 @@
+use echo_core::http::HeaderMap;
+use echo_core::{encoding_quality, parse_accept_encoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    #[cfg(feature = "compression-br")]
+    Brotli,
+    #[cfg(feature = "compression-zstd")]
+    Zstd,
+    #[cfg(feature = "compression-gzip")]
+    Gzip,
+    #[cfg(feature = "compression-deflate")]
+    Deflate,
+}
+
+impl Encoding {
+    /// 权重相同时按此顺序优先。
+    pub(crate) const ALL: &'static [Encoding] = &[
+        #[cfg(feature = "compression-br")]
+        Encoding::Brotli,
+        #[cfg(feature = "compression-zstd")]
+        Encoding::Zstd,
+        #[cfg(feature = "compression-gzip")]
+        Encoding::Gzip,
+        #[cfg(feature = "compression-deflate")]
+        Encoding::Deflate,
+    ];
+
+    /// 解析`Content-Encoding`中的编码名称，不区分大小写。
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        // `x-gzip`是`gzip`的别名。
+        let name = if name.eq_ignore_ascii_case("x-gzip") {
+            "gzip"
+        } else {
+            name
+        };
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.name().eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => "br",
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => "zstd",
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => "gzip",
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// 启用的压缩算法。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Encodings {
+    #[cfg(feature = "compression-br")]
+    pub(crate) br: bool,
+    #[cfg(feature = "compression-zstd")]
+    pub(crate) zstd: bool,
+    #[cfg(feature = "compression-gzip")]
+    pub(crate) gzip: bool,
+    #[cfg(feature = "compression-deflate")]
+    pub(crate) deflate: bool,
+}
+
+impl Default for Encodings {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "compression-br")]
+            br: true,
+            #[cfg(feature = "compression-zstd")]
+            zstd: true,
+            #[cfg(feature = "compression-gzip")]
+            gzip: true,
+            #[cfg(feature = "compression-deflate")]
+            deflate: true,
+        }
+    }
+}
+
+impl Encodings {
+    pub(crate) fn is_enabled(&self, encoding: Encoding) -> bool {
+        match encoding {
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => self.br,
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => self.zstd,
+            #[cfg(feature = "compression-gzip")]
+            Encoding::Gzip => self.gzip,
+            #[cfg(feature = "compression-deflate")]
+            Encoding::Deflate => self.deflate,
+        }
+    }
+
+    /// 根据`Accept-Encoding`选择权重最高的算法，没有可用的算法时返回`None`。
+    pub(crate) fn negotiate(&self, headers: &HeaderMap) -> Option<Encoding> {
+        let accepted = parse_accept_encoding(headers);
+        let mut best: Option<(Encoding, u16)> = None;
+        for &encoding in Encoding::ALL {
+            if !self.is_enabled(encoding) {
+                continue;
+            }
+            let q = encoding_quality(&accepted, encoding.name());
+            if q > 0 && best.is_none_or(|(_, best)| q > best) {
+                best = Some((encoding, q));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "compression-gzip",
+    feature = "compression-br",
+    feature = "compression-zstd"
+))]
+mod tests {
+    use echo_core::http::{header, HeaderMap, HeaderValue};
+
+    use super::{Encoding, Encodings};
+
+    fn negotiate(encodings: Encodings, accept_encoding: &'static str) -> Option<Encoding> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_static(accept_encoding),
+        );
+        encodings.negotiate(&headers)
+    }
+
+    #[test]
+    fn negotiate_q() {
+        let all = Encodings::default();
+        assert_eq!(negotiate(all, "gzip, br"), Some(Encoding::Brotli));
+        assert_eq!(negotiate(all, "gzip, br;q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(negotiate(all, "*"), Some(Encoding::Brotli));
+        assert_eq!(negotiate(all, "*, br;q=0"), Some(Encoding::Zstd));
+        assert_eq!(negotiate(all, "identity"), None);
+        assert_eq!(negotiate(all, "GZIP;q=1.5"), None);
+
+        let gzip_only = Encodings {
+            br: false,
+            zstd: false,
+            ..all
+        };
+        assert_eq!(negotiate(gzip_only, "br, gzip;q=0.1"), Some(Encoding::Gzip));
+    }
+}
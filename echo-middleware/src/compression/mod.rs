@@ -0,0 +1,411 @@
+mod decode;
+mod decompression;
+mod encode;
+mod encoding;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+pub use self::decode::DecompressionBody;
+pub use self::decompression::{Decompression, DecompressionFuture, DecompressionMiddleware};
+pub use self::encode::CompressionBody;
+use self::encoding::{Encoding, Encodings};
+
+/// 响应压缩，根据`Accept-Encoding`选择算法。
+///
+/// 每个数据帧压缩后立即发送，因此也适用于SSE等流式响应。
+/// 已经编码的响应、带有`Cache-Control: no-transform`的响应、
+/// 非文本类型以及小于`min_size`的响应不会被压缩。
+///
+/// 支持的算法由`compression-gzip`、`compression-deflate`、`compression-br`
+/// 和`compression-zstd`特性控制，启用的算法默认全部可用。
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionMiddleware {
+    encodings: Encodings,
+    min_size: u64,
+}
+
+impl Default for CompressionMiddleware {
+    fn default() -> Self {
+        Self {
+            encodings: Encodings::default(),
+            min_size: 32,
+        }
+    }
+}
+
+impl CompressionMiddleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    #[cfg(feature = "compression-gzip")]
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.encodings.gzip = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-deflate")]
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.encodings.deflate = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-br")]
+    pub fn br(mut self, enable: bool) -> Self {
+        self.encodings.br = enable;
+        self
+    }
+
+    #[cfg(feature = "compression-zstd")]
+    pub fn zstd(mut self, enable: bool) -> Self {
+        self.encodings.zstd = enable;
+        self
+    }
+
+    /// 已知长度小于该值的响应不压缩，默认为32字节。
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size = bytes;
+        self
+    }
+}
+
+impl<S> Middleware<S> for CompressionMiddleware {
+    type Service = Compression<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        Compression {
+            inner: service,
+            config: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Compression<S> {
+    inner: S,
+    config: CompressionMiddleware,
+}
+
+impl<S, B, ResB> Service<Request<B>> for Compression<S>
+where
+    S: Service<Request<B>, Response = echo_core::http::Response<ResB>>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = CompressionFuture<S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let encoding = self.config.encodings.negotiate(request.headers());
+        let head = request.method() == Method::HEAD;
+        CompressionFuture {
+            fut: self.inner.call(request),
+            encoding,
+            head,
+            min_size: self.config.min_size,
+        }
+    }
+}
+
+impl<S> fmt::Debug for Compression<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compression")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pin_project! {
+    pub struct CompressionFuture<F> {
+        #[pin]
+        fut: F,
+        encoding: Option<Encoding>,
+        head: bool,
+        min_size: u64,
+    }
+}
+
+impl<F, B, E> Future for CompressionFuture<F>
+where
+    F: Future<Output = Result<echo_core::http::Response<B>, E>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let response = ready!(this.fut.poll(cx))?;
+        Poll::Ready(Ok(compress(
+            response,
+            *this.encoding,
+            *this.head,
+            *this.min_size,
+        )))
+    }
+}
+
+impl<F> fmt::Debug for CompressionFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressionFuture")
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+fn compress<B>(
+    response: echo_core::http::Response<B>,
+    encoding: Option<Encoding>,
+    head: bool,
+    min_size: u64,
+) -> Response
+where
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    let (mut parts, body) = response.into_parts();
+    if !is_compressible(parts.status, &parts.headers) {
+        return Response::from_parts(parts, body.boxed());
+    }
+    parts
+        .headers
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+    let len = parts
+        .headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse::<u64>().ok())
+        .or_else(|| body.size_hint().exact());
+    let Some(encoding) = encoding.filter(|_| len.is_none_or(|len| len >= min_size)) else {
+        return Response::from_parts(parts, body.boxed());
+    };
+
+    let body = if head {
+        body.boxed()
+    } else {
+        CompressionBody::new(body, encoding).boxed()
+    };
+
+    let headers = &mut parts.headers;
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::ACCEPT_RANGES);
+    headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.name()),
+    );
+    // 压缩后内容不同，强ETag需要降级为弱ETag。
+    if let Some(etag) = headers.get(header::ETAG) {
+        if !etag.as_bytes().starts_with(b"W/") {
+            let mut weak = b"W/".to_vec();
+            weak.extend_from_slice(etag.as_bytes());
+            if let Ok(weak) = HeaderValue::from_bytes(&weak) {
+                headers.insert(header::ETAG, weak);
+            }
+        }
+    }
+    Response::from_parts(parts, body)
+}
+
+fn is_compressible(status: StatusCode, headers: &HeaderMap) -> bool {
+    if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || status == StatusCode::PARTIAL_CONTENT
+        || headers.contains_key(header::CONTENT_ENCODING)
+        || headers.contains_key(header::CONTENT_RANGE)
+    {
+        return false;
+    }
+
+    let no_transform = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"));
+    if no_transform {
+        return false;
+    }
+
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(is_compressible_type)
+}
+
+/// 文本类型适合压缩，图片、视频和压缩包等通常已经压缩过。
+fn is_compressible_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((ty, subtype)) = essence.split_once('/') else {
+        return false;
+    };
+    if ty == "text" {
+        return true;
+    }
+    if subtype.ends_with("+json") || subtype.ends_with("+xml") {
+        return true;
+    }
+    matches!(
+        (ty, subtype),
+        ("application", "json")
+            | ("application", "xml")
+            | ("application", "javascript")
+            | ("application", "ecmascript")
+            | ("application", "wasm")
+            | ("application", "x-javascript")
+    )
+}
+
+#[cfg(all(test, feature = "compression-gzip"))]
+mod tests {
+    use std::convert::Infallible;
+    use std::io::Read;
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::{header, HeaderValue, StatusCode};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use super::CompressionMiddleware;
+
+    const TEXT: &str = "hello world, hello world, hello world, hello world";
+
+    fn request(accept_encoding: &str) -> Request {
+        Request::builder()
+            .uri("/")
+            .header(header::ACCEPT_ENCODING, accept_encoding)
+            .body(Default::default())
+            .unwrap()
+    }
+
+    fn response(content_type: &'static str, body: &'static str) -> Response {
+        let mut response = body.into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        response
+            .headers_mut()
+            .insert(header::ETAG, HeaderValue::from_static("\"1\""));
+        response
+    }
+
+    #[tokio::test]
+    async fn gzip() {
+        let service =
+            service_fn(|_: Request| async { Ok::<_, Infallible>(response("text/plain", TEXT)) })
+                .with(CompressionMiddleware::new());
+
+        let response = service.call(request("gzip;q=0.5, identity")).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(headers[header::VARY], "accept-encoding");
+        assert_eq!(headers[header::ETAG], "W/\"1\"");
+        assert!(!headers.contains_key(header::CONTENT_LENGTH));
+
+        // 每个数据帧都会立即刷新。
+        let mut body = response.into_body();
+        let first = body.data().await.unwrap().unwrap();
+        assert!(!first.is_empty());
+        let rest = body.collect().bytes().await.unwrap();
+
+        let mut compressed = first.to_vec();
+        compressed.extend_from_slice(&rest);
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, TEXT);
+    }
+
+    #[tokio::test]
+    async fn skip() {
+        let service = service_fn(|request: Request| async move {
+            let response = match request.uri().path() {
+                "/small" => response("text/plain", "hi"),
+                "/image" => response("image/png", TEXT),
+                "/encoded" => {
+                    let mut response = response("text/plain", TEXT);
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_ENCODING, HeaderValue::from_static("br"));
+                    response
+                }
+                _ => response("text/plain", TEXT),
+            };
+            Ok::<_, Infallible>(response)
+        })
+        .with(CompressionMiddleware::new());
+
+        for path in ["/small", "/image", "/encoded"] {
+            let mut request = request("gzip");
+            *request.uri_mut() = path.parse().unwrap();
+            let response = service.call(request).await.unwrap();
+            assert_ne!(
+                response.headers().get(header::CONTENT_ENCODING),
+                Some(&HeaderValue::from_static("gzip"))
+            );
+        }
+
+        let response = service.call(request("gzip;q=0, identity")).await.unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, TEXT);
+    }
+
+    #[cfg(all(
+        feature = "compression-deflate",
+        feature = "compression-br",
+        feature = "compression-zstd"
+    ))]
+    #[tokio::test]
+    async fn encodings() {
+        let service = service_fn(|_: Request| async {
+            Ok::<_, Infallible>(response("application/json", TEXT))
+        })
+        .with(CompressionMiddleware::new());
+
+        for encoding in ["deflate", "br", "zstd"] {
+            let response = service.call(request(encoding)).await.unwrap();
+            assert_eq!(response.headers()[header::CONTENT_ENCODING], encoding);
+            let body = response.into_body().collect().bytes().await.unwrap();
+
+            let mut decoded = String::new();
+            match encoding {
+                "deflate" => flate2::read::ZlibDecoder::new(&body[..])
+                    .read_to_string(&mut decoded)
+                    .unwrap(),
+                "br" => brotli::Decompressor::new(&body[..], 4096)
+                    .read_to_string(&mut decoded)
+                    .unwrap(),
+                _ => zstd::stream::read::Decoder::new(&body[..])
+                    .unwrap()
+                    .read_to_string(&mut decoded)
+                    .unwrap(),
+            };
+            assert_eq!(decoded, TEXT);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use echo_core::body::{Body, Bytes, Frame, SizeHint};
+use pin_project_lite::pin_project;
+
+use super::limiter::Permit;
+
+/// 等待响应体发送完成的名额。
+pub(crate) struct Pending {
+    pub(crate) permit: Permit,
+    pub(crate) start: Instant,
+    pub(crate) success: bool,
+}
+
+impl Pending {
+    pub(crate) fn finish(self, success: bool) {
+        self.permit
+            .observe(self.start.elapsed(), self.success && success);
+    }
+}
+
+pin_project! {
+    /// 发送完成或出错时记录延迟并释放名额，被丢弃时只释放名额。
+    pub struct ConcurrencyLimitBody<B> {
+        #[pin]
+        inner: B,
+        pending: Option<Pending>,
+    }
+}
+
+impl<B> ConcurrencyLimitBody<B> {
+    pub(crate) fn new(inner: B, pending: Pending) -> Self {
+        Self {
+            inner,
+            pending: Some(pending),
+        }
+    }
+}
+
+impl<B: Body> Body for ConcurrencyLimitBody<B> {
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let this = self.project();
+        let frame = std::task::ready!(this.inner.poll_frame(cx));
+        let done = match &frame {
+            Some(Ok(_)) => None,
+            Some(Err(_)) => Some(false),
+            None => Some(true),
+        };
+        if let Some(success) = done {
+            if let Some(pending) = this.pending.take() {
+                pending.finish(success);
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> std::fmt::Debug for ConcurrencyLimitBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrencyLimitBody").finish()
+    }
+}
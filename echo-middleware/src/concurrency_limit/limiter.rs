@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+/// 并发限制器，自适应模式下根据观测到的延迟调整上限。
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    state: Mutex<State>,
+    notify: Notify,
+    max: usize,
+    target_latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct State {
+    limit: f64,
+    in_flight: usize,
+}
+
+/// 超过目标延迟时上限乘以该系数。
+const BACKOFF: f64 = 0.9;
+
+impl Limiter {
+    pub(crate) fn new(max: usize, target_latency: Option<Duration>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                limit: max as f64,
+                in_flight: 0,
+            }),
+            notify: Notify::new(),
+            max,
+            target_latency,
+        }
+    }
+
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight >= state.limit as usize {
+            return None;
+        }
+        state.in_flight += 1;
+        Some(Permit {
+            limiter: self.clone(),
+        })
+    }
+
+    /// 等待直到获取到许可。
+    pub(crate) async fn acquire(self: Arc<Self>) -> Permit {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // 先注册再检查，避免错过检查之后的释放。
+            notified.as_mut().enable();
+            if let Some(permit) = self.try_acquire() {
+                return permit;
+            }
+            notified.await;
+        }
+    }
+
+    /// 记录一次请求的结果，延迟不超过目标时线性增加上限，否则按比例减小。
+    pub(crate) fn observe(&self, latency: Duration, success: bool) {
+        let Some(target) = self.target_latency else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        let before = state.limit as usize;
+        if success && latency <= target {
+            state.limit = (state.limit + 1.0 / state.limit).min(self.max as f64);
+        } else {
+            state.limit = (state.limit * BACKOFF).max(1.0);
+        }
+        if state.limit as usize > before {
+            self.notify.notify_one();
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+}
+
+/// 持有期间占用一个并发名额。
+#[derive(Debug)]
+pub struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+impl Permit {
+    /// 记录请求结果后释放名额。
+    pub(crate) fn observe(self, latency: Duration, success: bool) {
+        self.limiter.observe(latency, success);
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().in_flight -= 1;
+        self.limiter.notify.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::Limiter;
+
+    #[test]
+    fn adaptive() {
+        let limiter = Arc::new(Limiter::new(4, Some(Duration::from_millis(100))));
+
+        for _ in 0..10 {
+            limiter.observe(Duration::from_millis(500), true);
+        }
+        assert_eq!(limiter.limit(), 1);
+        let permit = limiter.try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        drop(permit);
+
+        for _ in 0..10 {
+            limiter.observe(Duration::from_millis(10), true);
+        }
+        assert_eq!(limiter.limit(), 4);
+        limiter.observe(Duration::from_millis(10), false);
+        assert_eq!(limiter.limit(), 3);
+    }
+}
@@ -0,0 +1,297 @@
+mod body;
+mod limiter;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::http::StatusCode;
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+pub use self::body::ConcurrencyLimitBody;
+
+use self::body::Pending;
+use self::limiter::{Limiter, Permit};
+
+/// 并发限制中间件。
+///
+/// 限制同时处理的请求数，名额在响应体发送完成、出错或被丢弃时才释放，
+/// 因此流式响应在发送期间一直占用名额，自适应调整使用的延迟也包括发送响应体的时间。
+/// 每次`transform`都会创建独立的限制器，通过`Route::with`应用时每个路由单独计数，
+/// 应用在路由器外层时对所有请求计数。
+/// 默认在达到上限时立即返回503，也可以排队等待一段时间。
+#[derive(Debug, Clone, Copy)]
+pub struct ConcurrencyLimitMiddleware {
+    max: usize,
+    queue_timeout: Option<Duration>,
+    target_latency: Option<Duration>,
+}
+
+impl ConcurrencyLimitMiddleware {
+    pub fn new(max: usize) -> Self {
+        assert!(max > 0, "concurrency limit must be greater than zero");
+        Self {
+            max,
+            queue_timeout: None,
+            target_latency: None,
+        }
+    }
+
+    /// 达到上限时排队等待，超过`timeout`仍未获取到名额时返回503。
+    pub fn queue(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// 根据延迟自适应地调整上限。
+    ///
+    /// 请求成功且延迟不超过`target`时上限逐渐增加，直到`new`指定的最大值，
+    /// 否则按比例减小，最小为1。
+    pub fn adaptive(mut self, target: Duration) -> Self {
+        self.target_latency = Some(target);
+        self
+    }
+}
+
+impl<S> Middleware<S> for ConcurrencyLimitMiddleware {
+    type Service = ConcurrencyLimit<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        ConcurrencyLimit {
+            inner: Arc::new(service),
+            limiter: Arc::new(Limiter::new(self.max, self.target_latency)),
+            queue_timeout: self.queue_timeout,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ConcurrencyLimit<S> {
+    inner: Arc<S>,
+    limiter: Arc<Limiter>,
+    queue_timeout: Option<Duration>,
+}
+
+impl<S, B, ResB> Service<Request<B>> for ConcurrencyLimit<S>
+where
+    S: Service<Request, Response = echo_core::http::Response<ResB>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ConcurrencyLimitFuture<S, S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let request = request.map(BodyExt::boxed);
+
+        if let Some(permit) = self.limiter.try_acquire() {
+            return ConcurrencyLimitFuture::Inner {
+                fut: self.inner.call(request),
+                permit: Some(permit),
+                start: Instant::now(),
+            };
+        }
+
+        match self.queue_timeout {
+            Some(timeout) => {
+                let acquire = self.limiter.clone().acquire();
+                ConcurrencyLimitFuture::Queued {
+                    acquire: Box::pin(
+                        async move { tokio::time::timeout(timeout, acquire).await.ok() },
+                    ),
+                    inner: self.inner.clone(),
+                    request: Some(request),
+                }
+            }
+            None => ConcurrencyLimitFuture::Overloaded,
+        }
+    }
+}
+
+impl<S> fmt::Debug for ConcurrencyLimit<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrencyLimit")
+            .field("inner", &self.inner)
+            .field("limiter", &self.limiter)
+            .field("queue_timeout", &self.queue_timeout)
+            .finish()
+    }
+}
+
+pin_project! {
+    #[project = ConcurrencyLimitFutureProj]
+    pub enum ConcurrencyLimitFuture<S, F> {
+        Queued {
+            acquire: BoxFuture<'static, Option<Permit>>,
+            inner: Arc<S>,
+            request: Option<Request>,
+        },
+        Inner {
+            #[pin]
+            fut: F,
+            permit: Option<Permit>,
+            start: Instant,
+        },
+        Overloaded,
+    }
+}
+
+impl<S, B> Future for ConcurrencyLimitFuture<S, S::Future>
+where
+    S: Service<Request, Response = echo_core::http::Response<B>>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match self.as_mut().project() {
+                ConcurrencyLimitFutureProj::Queued {
+                    acquire,
+                    inner,
+                    request,
+                } => {
+                    let Some(permit) = ready!(acquire.as_mut().poll(cx)) else {
+                        return Poll::Ready(Ok(overloaded()));
+                    };
+                    let fut = inner.call(request.take().unwrap());
+                    self.set(ConcurrencyLimitFuture::Inner {
+                        fut,
+                        permit: Some(permit),
+                        start: Instant::now(),
+                    });
+                }
+                ConcurrencyLimitFutureProj::Inner { fut, permit, start } => {
+                    let result = ready!(fut.poll(cx));
+                    let permit = permit.take().expect("polled after completion");
+                    let response = match result {
+                        Ok(response) => response,
+                        Err(err) => {
+                            permit.observe(start.elapsed(), false);
+                            return Poll::Ready(Err(err));
+                        }
+                    };
+                    let pending = Pending {
+                        permit,
+                        start: *start,
+                        success: !response.status().is_server_error(),
+                    };
+                    // 空的响应体可能不会被轮询，直接释放名额。
+                    if response.body().size_hint().exact() == Some(0) {
+                        pending.finish(true);
+                        return Poll::Ready(Ok(response.map(BodyExt::boxed)));
+                    }
+                    let response =
+                        response.map(|body| ConcurrencyLimitBody::new(body, pending).boxed());
+                    return Poll::Ready(Ok(response));
+                }
+                ConcurrencyLimitFutureProj::Overloaded => return Poll::Ready(Ok(overloaded())),
+            }
+        }
+    }
+}
+
+impl<S, F> fmt::Debug for ConcurrencyLimitFuture<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConcurrencyLimitFuture::Queued { .. } => f.write_str("ConcurrencyLimitFuture::Queued"),
+            ConcurrencyLimitFuture::Inner { .. } => f.write_str("ConcurrencyLimitFuture::Inner"),
+            ConcurrencyLimitFuture::Overloaded => f.write_str("ConcurrencyLimitFuture::Overloaded"),
+        }
+    }
+}
+
+fn overloaded() -> Response {
+    StatusCode::SERVICE_UNAVAILABLE.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::StatusCode;
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use super::ConcurrencyLimitMiddleware;
+
+    async fn handler(_: Request) -> Result<Response, Infallible> {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        Ok(Response::default())
+    }
+
+    fn request() -> Request {
+        Request::builder().body(Default::default()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shed() {
+        let service = service_fn(handler).with(ConcurrencyLimitMiddleware::new(1));
+
+        let first = service.call(request());
+        let second = service.call(request());
+        let (first, second) = tokio::join!(first, second);
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // 之前的请求完成后释放名额。
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn streaming_body() {
+        let service =
+            service_fn(|_: Request| async { Ok::<_, Infallible>("hello".into_response()) })
+                .with(ConcurrencyLimitMiddleware::new(1));
+
+        // 响应体发送完成之前一直占用名额。
+        let response = service.call(request()).await.unwrap();
+        let overloaded = service.call(request()).await.unwrap();
+        assert_eq!(overloaded.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "hello");
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 响应体被丢弃时也会释放名额。
+        drop(response);
+        let response = service.call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue() {
+        let service = service_fn(handler)
+            .with(ConcurrencyLimitMiddleware::new(1).queue(Duration::from_millis(1500)));
+
+        let (first, second, third) = tokio::join!(
+            service.call(request()),
+            service.call(request()),
+            service.call(request()),
+        );
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.unwrap().status(), StatusCode::OK);
+        assert_eq!(third.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,462 @@
+mod origin;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use echo_core::http::header::{self, HeaderName};
+use echo_core::http::{HeaderMap, HeaderValue, Method, Response};
+use echo_core::service::{Middleware, Service};
+use echo_core::Request;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use self::origin::OriginRule;
+
+/// 跨域资源共享（CORS）。
+///
+/// 预检请求（带有`Access-Control-Request-Method`的`OPTIONS`请求）直接由中间件响应，
+/// 不会到达内部服务，因此应当包裹在`Router`外层，避免路由因为没有注册`OPTIONS`而返回错误。
+///
+/// 默认不允许任何来源，需要通过`allow_*`方法配置。
+#[derive(Debug, Clone, Default)]
+pub struct CorsMiddleware {
+    config: Arc<Config>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Config {
+    origins: Vec<OriginRule>,
+    methods: Allow<Method>,
+    headers: Allow<HeaderName>,
+    expose_headers: Vec<HeaderName>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+enum Allow<T> {
+    List(Vec<T>),
+    /// 回显请求中的值。
+    Mirror,
+}
+
+impl<T> Default for Allow<T> {
+    fn default() -> Self {
+        Allow::List(Vec::new())
+    }
+}
+
+impl CorsMiddleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 允许任何来源、方法和请求头。
+    pub fn permissive() -> Self {
+        Self::new()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header()
+    }
+
+    /// # Panics
+    ///
+    /// If `origin` isn't a valid [`HeaderValue`].
+    pub fn allow_origin(self, origin: &str) -> Self {
+        let origin = HeaderValue::try_from(origin).expect("invalid origin");
+        self.rule(OriginRule::Exact(origin))
+    }
+
+    pub fn allow_origins<I, T>(self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        origins
+            .into_iter()
+            .fold(self, |cors, origin| cors.allow_origin(origin.as_ref()))
+    }
+
+    /// 使用通配符匹配来源，`*`匹配任意字符，例如`https://*.example.com`。
+    pub fn allow_origin_pattern(self, pattern: &str) -> Self {
+        self.rule(OriginRule::Pattern(pattern.into()))
+    }
+
+    pub fn allow_origin_fn<F>(self, f: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.rule(OriginRule::Predicate(Arc::new(f)))
+    }
+
+    /// 允许任何来源。启用凭证时回显请求的`Origin`，否则响应`*`。
+    pub fn allow_any_origin(self) -> Self {
+        self.rule(OriginRule::Any)
+    }
+
+    pub fn allow_methods<I>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        let config = Arc::make_mut(&mut self.config);
+        match &mut config.methods {
+            Allow::List(list) => list.extend(methods),
+            Allow::Mirror => {}
+        }
+        self
+    }
+
+    /// 允许预检请求中的任何方法。
+    pub fn allow_any_method(mut self) -> Self {
+        Arc::make_mut(&mut self.config).methods = Allow::Mirror;
+        self
+    }
+
+    pub fn allow_headers<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        let config = Arc::make_mut(&mut self.config);
+        match &mut config.headers {
+            Allow::List(list) => list.extend(headers),
+            Allow::Mirror => {}
+        }
+        self
+    }
+
+    /// 允许预检请求中的任何请求头。
+    pub fn allow_any_header(mut self) -> Self {
+        Arc::make_mut(&mut self.config).headers = Allow::Mirror;
+        self
+    }
+
+    /// 允许浏览器脚本读取的响应头。
+    pub fn expose_headers<I>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        Arc::make_mut(&mut self.config)
+            .expose_headers
+            .extend(headers);
+        self
+    }
+
+    pub fn allow_credentials(mut self, enable: bool) -> Self {
+        Arc::make_mut(&mut self.config).credentials = enable;
+        self
+    }
+
+    /// 预检结果的缓存时间。
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        Arc::make_mut(&mut self.config).max_age = Some(max_age);
+        self
+    }
+
+    fn rule(mut self, rule: OriginRule) -> Self {
+        Arc::make_mut(&mut self.config).origins.push(rule);
+        self
+    }
+}
+
+impl<S> Middleware<S> for CorsMiddleware {
+    type Service = Cors<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        Cors {
+            inner: service,
+            config: self.config,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Cors<S> {
+    inner: S,
+    config: Arc<Config>,
+}
+
+impl<S, B, ResB> Service<Request<B>> for Cors<S>
+where
+    S: Service<Request<B>, Response = Response<ResB>>,
+    ResB: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = CorsFuture<S::Future, ResB>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let config = &self.config;
+        let origin = request.headers().get(header::ORIGIN);
+
+        let preflight = request.method() == Method::OPTIONS
+            && origin.is_some()
+            && request
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::VARY, HeaderValue::from_static("origin"));
+
+        let allow_origin = origin.and_then(|origin| config.allow_origin(origin));
+        let allowed = allow_origin.is_some();
+        if let Some(allow_origin) = allow_origin {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+            if config.credentials {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                    HeaderValue::from_static("true"),
+                );
+            }
+        }
+
+        if preflight {
+            headers.append(
+                header::VARY,
+                HeaderValue::from_static("access-control-request-method"),
+            );
+            headers.append(
+                header::VARY,
+                HeaderValue::from_static("access-control-request-headers"),
+            );
+            if allowed {
+                config.preflight_headers(request.headers(), &mut headers);
+            }
+
+            let mut response = Response::new(ResB::default());
+            *response.headers_mut() = headers;
+            return CorsFuture::Preflight {
+                response: Some(response),
+            };
+        }
+
+        if allowed {
+            if let Some(expose) = join(&config.expose_headers) {
+                headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, expose);
+            }
+        }
+
+        CorsFuture::Inner {
+            fut: self.inner.call(request),
+            headers: Some(headers),
+        }
+    }
+}
+
+impl Config {
+    /// 来源被允许时返回`Access-Control-Allow-Origin`的值。
+    fn allow_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        let rule = self.origins.iter().find(|rule| rule.matches(origin))?;
+        if matches!(rule, OriginRule::Any) && !self.credentials {
+            Some(HeaderValue::from_static("*"))
+        } else {
+            Some(origin.clone())
+        }
+    }
+
+    fn preflight_headers(&self, request: &HeaderMap, headers: &mut HeaderMap) {
+        let methods = match &self.methods {
+            Allow::List(methods) => join(methods),
+            Allow::Mirror => request.get(header::ACCESS_CONTROL_REQUEST_METHOD).cloned(),
+        };
+        if let Some(methods) = methods {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+        }
+
+        let allow_headers = match &self.headers {
+            Allow::List(names) => join(names),
+            Allow::Mirror => request.get(header::ACCESS_CONTROL_REQUEST_HEADERS).cloned(),
+        };
+        if let Some(allow_headers) = allow_headers {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+        }
+
+        if let Some(max_age) = self.max_age {
+            headers.insert(
+                header::ACCESS_CONTROL_MAX_AGE,
+                HeaderValue::from(max_age.as_secs()),
+            );
+        }
+    }
+}
+
+fn join<T: AsRef<str>>(values: &[T]) -> Option<HeaderValue> {
+    if values.is_empty() {
+        return None;
+    }
+    let joined = values
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::try_from(joined).ok()
+}
+
+impl<S> fmt::Debug for Cors<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pin_project! {
+    #[project = CorsFutureProj]
+    pub enum CorsFuture<F, B> {
+        Inner {
+            #[pin]
+            fut: F,
+            headers: Option<HeaderMap>,
+        },
+        Preflight {
+            response: Option<Response<B>>,
+        },
+    }
+}
+
+impl<F, B, E> Future for CorsFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            CorsFutureProj::Inner { fut, headers } => {
+                let mut response = ready!(fut.poll(cx))?;
+                let headers = headers.take().expect("polled after completion");
+                for (name, value) in headers.iter() {
+                    if name == header::VARY {
+                        response.headers_mut().append(name, value.clone());
+                    } else {
+                        response.headers_mut().insert(name, value.clone());
+                    }
+                }
+                Poll::Ready(Ok(response))
+            }
+            CorsFutureProj::Preflight { response } => {
+                Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
+
+impl<F, B> fmt::Debug for CorsFuture<F, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorsFuture::Inner { .. } => f.write_str("CorsFuture::Inner"),
+            CorsFuture::Preflight { .. } => f.write_str("CorsFuture::Preflight"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use echo_core::http::{header, Method, StatusCode};
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use super::CorsMiddleware;
+
+    fn request(method: Method, headers: &[(header::HeaderName, &str)]) -> Request {
+        let mut builder = Request::builder().method(method).uri("/");
+        for (name, value) in headers {
+            builder = builder.header(name, *value);
+        }
+        builder.body(Default::default()).unwrap()
+    }
+
+    async fn teapot(_: Request) -> Result<Response, Infallible> {
+        let mut response = Response::default();
+        *response.status_mut() = StatusCode::IM_A_TEAPOT;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn preflight() {
+        let service = service_fn(teapot).with(
+            CorsMiddleware::new()
+                .allow_origin("https://a.com")
+                .allow_methods([Method::GET, Method::POST])
+                .allow_any_header()
+                .max_age(Duration::from_secs(600)),
+        );
+
+        let response = service
+            .call(request(
+                Method::OPTIONS,
+                &[
+                    (header::ORIGIN, "https://a.com"),
+                    (header::ACCESS_CONTROL_REQUEST_METHOD, "POST"),
+                    (header::ACCESS_CONTROL_REQUEST_HEADERS, "x-token"),
+                ],
+            ))
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://a.com"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "GET, POST");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "x-token");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+
+        let response = service
+            .call(request(
+                Method::OPTIONS,
+                &[
+                    (header::ORIGIN, "https://b.com"),
+                    (header::ACCESS_CONTROL_REQUEST_METHOD, "POST"),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn simple() {
+        let service = service_fn(teapot).with(
+            CorsMiddleware::new()
+                .allow_origin_pattern("https://*.a.com")
+                .allow_credentials(true)
+                .expose_headers([header::ETAG]),
+        );
+
+        let response = service
+            .call(request(Method::GET, &[(header::ORIGIN, "https://x.a.com")]))
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://x.a.com"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_EXPOSE_HEADERS], "etag");
+        assert_eq!(headers[header::VARY], "origin");
+
+        let service = service_fn(teapot).with(CorsMiddleware::permissive());
+        let response = service
+            .call(request(Method::GET, &[(header::ORIGIN, "https://b.com")]))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+use std::sync::Arc;
+
+use echo_core::http::HeaderValue;
+
+/// 允许的来源规则，任一规则匹配即允许。
+#[derive(Clone)]
+pub(crate) enum OriginRule {
+    Any,
+    Exact(HeaderValue),
+    /// `*`匹配任意字符，例如`https://*.example.com`。
+    Pattern(Arc<str>),
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl OriginRule {
+    pub(crate) fn matches(&self, origin: &HeaderValue) -> bool {
+        match self {
+            OriginRule::Any => true,
+            OriginRule::Exact(value) => value == origin,
+            OriginRule::Pattern(pattern) => origin
+                .to_str()
+                .is_ok_and(|origin| wildcard_match(pattern, origin)),
+            OriginRule::Predicate(f) => origin.to_str().is_ok_and(|origin| f(origin)),
+        }
+    }
+}
+
+impl fmt::Debug for OriginRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OriginRule::Any => f.write_str("Any"),
+            OriginRule::Exact(value) => f.debug_tuple("Exact").field(value).finish(),
+            OriginRule::Pattern(pattern) => f.debug_tuple("Pattern").field(pattern).finish(),
+            OriginRule::Predicate(_) => f.write_str("Predicate"),
+        }
+    }
+}
+
+/// 不区分大小写的通配符匹配，`*`匹配任意长度的字符。
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p].eq_ignore_ascii_case(&text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((bp, bt)) = backtrack {
+            p = bp + 1;
+            t = bt + 1;
+            backtrack = Some((bp, bt + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|b| *b == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wildcard_match;
+
+    #[test]
+    fn wildcard() {
+        assert!(wildcard_match(
+            "https://*.example.com",
+            "https://a.example.com"
+        ));
+        assert!(wildcard_match(
+            "https://*.example.com",
+            "https://a.b.Example.com"
+        ));
+        assert!(!wildcard_match(
+            "https://*.example.com",
+            "https://example.com"
+        ));
+        assert!(!wildcard_match(
+            "https://*.example.com",
+            "https://a.example.com.evil"
+        ));
+        assert!(wildcard_match(
+            "http://localhost:*",
+            "http://localhost:8080"
+        ));
+    }
+}
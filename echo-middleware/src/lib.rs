@@ -6,3 +6,35 @@ pub mod core;
 
 #[cfg(feature = "util")]
 pub mod util;
+
+#[cfg(feature = "cors")]
+pub mod cors;
+
+#[cfg(any(
+    feature = "compression-gzip",
+    feature = "compression-deflate",
+    feature = "compression-br",
+    feature = "compression-zstd"
+))]
+pub mod compression;
+
+#[cfg(feature = "timeout")]
+pub mod timeout;
+
+#[cfg(feature = "request-id")]
+pub mod request_id;
+
+#[cfg(feature = "access-log")]
+pub mod access_log;
+
+#[cfg(feature = "tracing")]
+pub mod trace;
+
+#[cfg(feature = "rate-limit")]
+pub mod rate_limit;
+
+#[cfg(feature = "concurrency-limit")]
+pub mod concurrency_limit;
+
+#[cfg(feature = "body-limit")]
+pub mod body_limit;
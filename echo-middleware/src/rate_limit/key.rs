@@ -0,0 +1,92 @@
+use std::fmt;
+use std::sync::Arc;
+
+use echo_core::http::header::HeaderName;
+use echo_core::http::request::Parts;
+use echo_core::RemoteAddr;
+
+/// 从请求中提取限流的键，提取不到键的请求不受限制。
+#[derive(Clone)]
+pub struct RateLimitKey {
+    kind: Kind,
+}
+
+#[derive(Clone)]
+enum Kind {
+    RemoteAddr,
+    Header(HeaderName),
+    Extension(&'static str, ExtractFn),
+    Custom(ExtractFn),
+}
+
+type ExtractFn = Arc<dyn Fn(&Parts) -> Option<String> + Send + Sync>;
+
+impl Default for RateLimitKey {
+    fn default() -> Self {
+        Self::remote_addr()
+    }
+}
+
+impl RateLimitKey {
+    /// 客户端的IP地址，不包括端口。
+    pub fn remote_addr() -> Self {
+        Self {
+            kind: Kind::RemoteAddr,
+        }
+    }
+
+    /// 请求头的值，例如API密钥。
+    pub fn header(name: HeaderName) -> Self {
+        Self {
+            kind: Kind::Header(name),
+        }
+    }
+
+    /// 请求扩展中的值，例如认证中间件插入的用户ID。
+    pub fn extension<T>() -> Self
+    where
+        T: fmt::Display + Send + Sync + 'static,
+    {
+        Self {
+            kind: Kind::Extension(
+                std::any::type_name::<T>(),
+                Arc::new(|parts| parts.extensions.get::<T>().map(T::to_string)),
+            ),
+        }
+    }
+
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&Parts) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            kind: Kind::Custom(Arc::new(f)),
+        }
+    }
+
+    pub(crate) fn extract(&self, parts: &Parts) -> Option<String> {
+        match &self.kind {
+            Kind::RemoteAddr => parts
+                .extensions
+                .get::<RemoteAddr>()
+                .map(|addr| addr.0.ip().to_string()),
+            Kind::Header(name) => parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            Kind::Extension(_, f) | Kind::Custom(f) => f(parts),
+        }
+    }
+}
+
+impl fmt::Debug for RateLimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            Kind::RemoteAddr => f.write_str("RemoteAddr"),
+            Kind::Header(name) => f.debug_tuple("Header").field(name).finish(),
+            Kind::Extension(name, _) => f.debug_tuple("Extension").field(name).finish(),
+            Kind::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
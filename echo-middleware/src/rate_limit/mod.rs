@@ -0,0 +1,349 @@
+mod key;
+mod quota;
+mod store;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::http::header::{self, HeaderName};
+use echo_core::http::{HeaderMap, HeaderValue, StatusCode};
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+pub use self::key::RateLimitKey;
+pub use self::quota::{Algorithm, Decision, Quota};
+pub use self::store::{MemoryStore, RateLimitStore};
+
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// 限流中间件。
+///
+/// 默认按客户端IP限流，使用[`MemoryStore`]保存状态。
+/// 允许的请求在响应中添加`RateLimit-Limit`、`RateLimit-Remaining`和`RateLimit-Reset`头，
+/// 超出配额时返回[`RateLimitError`]，可以通过`CatchErrorMiddleware`将其转换为429响应。
+/// 多个中间件共享同一个存储时，键不能重复。
+#[derive(Clone)]
+pub struct RateLimitMiddleware {
+    quota: Quota,
+    key: RateLimitKey,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            key: RateLimitKey::default(),
+            store: Arc::new(MemoryStore::new()),
+        }
+    }
+
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// 使用自定义的存储，传入`Arc`时可以与其他中间件共享。
+    pub fn store<T>(mut self, store: T) -> Self
+    where
+        T: RateLimitStore,
+    {
+        self.store = Arc::new(store);
+        self
+    }
+}
+
+impl fmt::Debug for RateLimitMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("quota", &self.quota)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<S> Middleware<S> for RateLimitMiddleware {
+    type Service = RateLimit<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        RateLimit {
+            inner: Arc::new(service),
+            config: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: Arc<S>,
+    config: RateLimitMiddleware,
+}
+
+impl<S, B, ResB> Service<Request<B>> for RateLimit<S>
+where
+    S: Service<Request, Response = echo_core::http::Response<ResB>>,
+    S::Error: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = BoxError;
+    type Future = RateLimitFuture<S, S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let (parts, body) = request.into_parts();
+        let key = self.config.key.extract(&parts);
+        let request = Request::from_parts(parts, body.boxed());
+
+        match key {
+            Some(key) => RateLimitFuture::Check {
+                check: self.config.store.check(key, self.config.quota),
+                inner: self.inner.clone(),
+                request: Some(request),
+            },
+            None => RateLimitFuture::Inner {
+                fut: self.inner.call(request),
+                decision: None,
+            },
+        }
+    }
+}
+
+impl<S> fmt::Debug for RateLimit<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pin_project! {
+    #[project = RateLimitFutureProj]
+    pub enum RateLimitFuture<S, F> {
+        Check {
+            check: BoxFuture<'static, Result<Decision, BoxError>>,
+            inner: Arc<S>,
+            request: Option<Request>,
+        },
+        Inner {
+            #[pin]
+            fut: F,
+            decision: Option<Decision>,
+        },
+    }
+}
+
+impl<S, B> Future for RateLimitFuture<S, S::Future>
+where
+    S: Service<Request, Response = echo_core::http::Response<B>>,
+    S::Error: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, BoxError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match self.as_mut().project() {
+                RateLimitFutureProj::Check {
+                    check,
+                    inner,
+                    request,
+                } => {
+                    let decision = ready!(check.as_mut().poll(cx))?;
+                    if !decision.allowed {
+                        return Poll::Ready(Err(RateLimitError(decision).into()));
+                    }
+                    let fut = inner.call(request.take().unwrap());
+                    self.set(RateLimitFuture::Inner {
+                        fut,
+                        decision: Some(decision),
+                    });
+                }
+                RateLimitFutureProj::Inner { fut, decision } => {
+                    let response = ready!(fut.poll(cx)).map_err(Into::into)?;
+                    let mut response = response.map(BodyExt::boxed);
+                    if let Some(decision) = decision {
+                        insert_headers(response.headers_mut(), decision);
+                    }
+                    return Poll::Ready(Ok(response));
+                }
+            }
+        }
+    }
+}
+
+impl<S, F> fmt::Debug for RateLimitFuture<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitFuture::Check { .. } => f.write_str("RateLimitFuture::Check"),
+            RateLimitFuture::Inner { decision, .. } => f
+                .debug_struct("RateLimitFuture::Inner")
+                .field("decision", decision)
+                .finish(),
+        }
+    }
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(RATELIMIT_LIMIT, decision.limit.into());
+    headers.insert(RATELIMIT_REMAINING, decision.remaining.into());
+    headers.insert(RATELIMIT_RESET, ceil_secs(decision.reset).into());
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// 超出限流配额。
+///
+/// 转换为响应时返回429，并带有`Retry-After`和`RateLimit-*`头。
+#[derive(Debug, Clone)]
+pub struct RateLimitError(Decision);
+
+impl RateLimitError {
+    pub fn decision(&self) -> &Decision {
+        &self.0
+    }
+
+    /// 距离下次允许请求的时间。
+    pub fn retry_after(&self) -> Duration {
+        self.0.retry_after
+    }
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("rate limit exceeded")
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+impl IntoResponse for RateLimitError {
+    fn into_response(self) -> Response {
+        let mut headers = HeaderMap::new();
+        insert_headers(&mut headers, &self.0);
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(self.0.retry_after)),
+        );
+        (StatusCode::TOO_MANY_REQUESTS, headers, "Too Many Requests").into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use echo_core::http::header::HeaderName;
+    use echo_core::http::{header, StatusCode};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{RemoteAddr, Request};
+
+    use super::{
+        MemoryStore, Quota, RateLimitError, RateLimitKey, RateLimitMiddleware, RateLimitStore,
+    };
+
+    fn request(addr: &str) -> Request {
+        let mut request = Request::builder()
+            .header("x-api-key", "secret")
+            .body(Default::default())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(RemoteAddr(addr.parse().unwrap()));
+        request
+    }
+
+    #[tokio::test]
+    async fn remote_addr() {
+        let service =
+            service_fn(|_: Request| async { Ok::<_, Infallible>("hello".into_response()) }).with(
+                RateLimitMiddleware::new(Quota::token_bucket(2, Duration::from_secs(60))),
+            );
+
+        let response = service.call(request("10.0.0.1:1000")).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["ratelimit-limit"], "2");
+        assert_eq!(headers["ratelimit-remaining"], "1");
+        assert_eq!(headers["ratelimit-reset"], "30");
+
+        // 端口不同的同一IP共享配额。
+        assert!(service.call(request("10.0.0.1:2000")).await.is_ok());
+        let err = service.call(request("10.0.0.1:3000")).await.unwrap_err();
+        let err = err.downcast::<RateLimitError>().unwrap();
+        assert!(err.retry_after() <= Duration::from_secs(30));
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+        assert_eq!(response.headers()["ratelimit-remaining"], "0");
+
+        assert!(service.call(request("10.0.0.2:1000")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn key() {
+        let service =
+            service_fn(|_: Request| async { Ok::<_, Infallible>("hello".into_response()) }).with(
+                RateLimitMiddleware::new(Quota::sliding_window(1, Duration::from_secs(60)))
+                    .key(RateLimitKey::header(HeaderName::from_static("x-api-key"))),
+            );
+
+        assert!(service.call(request("10.0.0.1:1000")).await.is_ok());
+        assert!(service.call(request("10.0.0.2:1000")).await.is_err());
+
+        // 没有键的请求不受限制。
+        let request: Request = Request::builder().body(Default::default()).unwrap();
+        let response = service.call(request).await.unwrap();
+        assert!(!response.headers().contains_key("ratelimit-limit"));
+    }
+
+    #[tokio::test]
+    async fn shared_store() {
+        fn middleware<T: RateLimitStore>(store: T) -> RateLimitMiddleware {
+            RateLimitMiddleware::new(Quota::token_bucket(2, Duration::from_secs(60))).store(store)
+        }
+
+        let store = Arc::new(MemoryStore::new());
+        let a = service_fn(|_: Request| async { Ok::<_, Infallible>("a".into_response()) })
+            .with(middleware(store.clone()));
+        let b = service_fn(|_: Request| async { Ok::<_, Infallible>("b".into_response()) })
+            .with(middleware(store.clone()));
+
+        // 两个中间件消耗同一份配额。
+        assert!(a.call(request("10.0.0.1:1000")).await.is_ok());
+        let response = b.call(request("10.0.0.1:1000")).await.unwrap();
+        assert_eq!(response.headers()["ratelimit-remaining"], "0");
+        assert!(a.call(request("10.0.0.1:1000")).await.is_err());
+        assert!(b.call(request("10.0.0.1:1000")).await.is_err());
+
+        // 存储也可以作为trait对象共享。
+        let store: Arc<dyn RateLimitStore> = store;
+        let c = service_fn(|_: Request| async { Ok::<_, Infallible>("c".into_response()) })
+            .with(middleware(store));
+        assert!(c.call(request("10.0.0.1:1000")).await.is_err());
+        assert!(c.call(request("10.0.0.2:1000")).await.is_ok());
+    }
+}
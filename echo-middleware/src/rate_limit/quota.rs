@@ -0,0 +1,234 @@
+use std::time::{Duration, Instant};
+
+/// 限流算法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// 令牌桶，允许突发`limit`个请求，之后按`limit / period`的速率恢复。
+    TokenBucket,
+    /// 滑动窗口，按上一个窗口的计数加权估算最近`period`内的请求数。
+    SlidingWindow,
+}
+
+/// 限流配额。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    limit: u64,
+    period: Duration,
+    algorithm: Algorithm,
+}
+
+impl Quota {
+    /// 桶容量为`capacity`，每`period`恢复`capacity`个令牌。
+    pub fn token_bucket(capacity: u64, period: Duration) -> Self {
+        Self::new(capacity, period, Algorithm::TokenBucket)
+    }
+
+    /// 每个长度为`window`的窗口内最多`limit`个请求。
+    pub fn sliding_window(limit: u64, window: Duration) -> Self {
+        Self::new(limit, window, Algorithm::SlidingWindow)
+    }
+
+    fn new(limit: u64, period: Duration, algorithm: Algorithm) -> Self {
+        assert!(limit > 0, "rate limit must be greater than zero");
+        assert!(!period.is_zero(), "rate limit period must not be zero");
+        Self {
+            limit,
+            period,
+            algorithm,
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+/// 一次检查的结果，自定义存储需要构造该结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    /// 是否允许本次请求。
+    pub allowed: bool,
+    pub limit: u64,
+    /// 本次请求之后剩余的配额。
+    pub remaining: u64,
+    /// 配额完全恢复的剩余时间。
+    pub reset: Duration,
+    /// 被拒绝时距离下次允许请求的时间，允许时为零。
+    pub retry_after: Duration,
+}
+
+/// 单个键的限流状态。
+#[derive(Debug, Clone)]
+pub(crate) enum State {
+    TokenBucket {
+        tokens: f64,
+        last: Instant,
+    },
+    SlidingWindow {
+        start: Instant,
+        previous: u64,
+        current: u64,
+    },
+}
+
+impl State {
+    pub(crate) fn new(quota: &Quota, now: Instant) -> Self {
+        match quota.algorithm {
+            Algorithm::TokenBucket => State::TokenBucket {
+                tokens: quota.limit as f64,
+                last: now,
+            },
+            Algorithm::SlidingWindow => State::SlidingWindow {
+                start: now,
+                previous: 0,
+                current: 0,
+            },
+        }
+    }
+
+    /// 检查并消耗一个配额。
+    pub(crate) fn check(&mut self, quota: &Quota, now: Instant) -> Decision {
+        let limit = quota.limit;
+        let period = quota.period.as_secs_f64();
+
+        match self {
+            State::TokenBucket { tokens, last } => {
+                let rate = limit as f64 / period;
+                let elapsed = now.saturating_duration_since(*last).as_secs_f64();
+                *tokens = (*tokens + elapsed * rate).min(limit as f64);
+                *last = now;
+
+                let allowed = *tokens >= 1.0;
+                if allowed {
+                    *tokens -= 1.0;
+                }
+                Decision {
+                    allowed,
+                    limit,
+                    remaining: *tokens as u64,
+                    reset: secs((limit as f64 - *tokens) / rate),
+                    retry_after: if allowed {
+                        Duration::ZERO
+                    } else {
+                        secs((1.0 - *tokens) / rate)
+                    },
+                }
+            }
+            State::SlidingWindow {
+                start,
+                previous,
+                current,
+            } => {
+                let elapsed = now.saturating_duration_since(*start).as_nanos();
+                let windows = elapsed / quota.period.as_nanos();
+                if windows == 1 {
+                    *previous = *current;
+                    *current = 0;
+                    *start += quota.period;
+                } else if windows > 1 {
+                    *previous = 0;
+                    *current = 0;
+                    let offset = elapsed % quota.period.as_nanos();
+                    *start = now - Duration::from_nanos(offset as u64);
+                }
+
+                let progress = now.saturating_duration_since(*start).as_secs_f64() / period;
+                let estimate = |previous: u64, current: u64, progress: f64| {
+                    previous as f64 * (1.0 - progress) + current as f64
+                };
+
+                let allowed = estimate(*previous, *current + 1, progress) <= limit as f64;
+                if allowed {
+                    *current += 1;
+                }
+                let used = estimate(*previous, *current, progress).ceil() as u64;
+
+                // 当前窗口结束后计数变为上一个窗口的计数，并随时间线性衰减。
+                let remaining_window = (1.0 - progress) * period;
+                let reset = if *current == 0 {
+                    remaining_window
+                } else {
+                    remaining_window + period
+                };
+                let retry_after = if allowed {
+                    0.0
+                } else if *current < limit {
+                    // 当前窗口内等待上一个窗口的权重衰减。
+                    let target = 1.0 - (limit - *current - 1) as f64 / *previous as f64;
+                    (target - progress).max(0.0) * period
+                } else {
+                    // 等到下一个窗口，当前窗口的计数衰减到足够低。
+                    let target = 1.0 - (limit - 1) as f64 / *current as f64;
+                    remaining_window + target.max(0.0) * period
+                };
+
+                Decision {
+                    allowed,
+                    limit,
+                    remaining: limit.saturating_sub(used),
+                    reset: secs(reset),
+                    retry_after: secs(retry_after),
+                }
+            }
+        }
+    }
+}
+
+fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Quota, State};
+
+    #[test]
+    fn token_bucket() {
+        let quota = Quota::token_bucket(2, Duration::from_secs(2));
+        let now = Instant::now();
+        let mut state = State::new(&quota, now);
+
+        assert_eq!(state.check(&quota, now).remaining, 1);
+        assert_eq!(state.check(&quota, now).remaining, 0);
+        let decision = state.check(&quota, now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_secs(1));
+        assert_eq!(decision.reset, Duration::from_secs(2));
+
+        let decision = state.check(&quota, now + Duration::from_secs(1));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+    }
+
+    #[test]
+    fn sliding_window() {
+        let quota = Quota::sliding_window(4, Duration::from_secs(10));
+        let now = Instant::now();
+        let mut state = State::new(&quota, now);
+
+        for remaining in (0..4).rev() {
+            assert_eq!(state.check(&quota, now).remaining, remaining);
+        }
+        let decision = state.check(&quota, now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_millis(12500));
+
+        // 下一个窗口过半时上一个窗口的4个请求按一半计算。
+        let now = now + Duration::from_secs(15);
+        assert!(state.check(&quota, now).allowed);
+        assert!(state.check(&quota, now).allowed);
+        let decision = state.check(&quota, now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_millis(2500));
+    }
+}
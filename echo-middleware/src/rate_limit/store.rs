@@ -0,0 +1,136 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use echo_core::service::future::BoxFuture;
+use echo_core::BoxError;
+
+use super::quota::{Decision, Quota, State};
+
+/// 限流状态的存储。
+///
+/// 检查和消耗配额必须是原子的，外部存储通常需要在服务端以脚本的形式实现算法。
+pub trait RateLimitStore: Send + Sync + 'static {
+    fn check(&self, key: String, quota: Quota) -> BoxFuture<'static, Result<Decision, BoxError>>;
+}
+
+/// 多个中间件可以通过`Arc`共享同一个存储。
+impl<T> RateLimitStore for Arc<T>
+where
+    T: RateLimitStore + ?Sized,
+{
+    fn check(&self, key: String, quota: Quota) -> BoxFuture<'static, Result<Decision, BoxError>> {
+        (**self).check(key, quota)
+    }
+}
+
+/// 分片的内存存储。
+///
+/// 每个分片由独立的锁保护，配额完全恢复的键会被定期清理。
+pub struct MemoryStore {
+    hasher: RandomState,
+    shards: Box<[Mutex<Shard>]>,
+}
+
+#[derive(Default)]
+struct Shard {
+    entries: HashMap<String, Entry>,
+    checks: u32,
+}
+
+struct Entry {
+    state: State,
+    expires: Instant,
+}
+
+/// 每个分片每检查这么多次清理一次过期的键。
+const SWEEP_INTERVAL: u32 = 1024;
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::with_shards(64)
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        assert!(shards > 0, "shards must be greater than zero");
+        Self {
+            hasher: RandomState::new(),
+            shards: (0..shards).map(|_| Default::default()).collect(),
+        }
+    }
+
+    pub(crate) fn check_at(&self, key: String, quota: &Quota, now: Instant) -> Decision {
+        let index = self.hasher.hash_one(&key) as usize % self.shards.len();
+        let mut shard = self.shards[index].lock().unwrap();
+
+        shard.checks += 1;
+        if shard.checks >= SWEEP_INTERVAL {
+            shard.checks = 0;
+            shard.entries.retain(|_, entry| entry.expires > now);
+        }
+
+        let entry = shard.entries.entry(key).or_insert_with(|| Entry {
+            state: State::new(quota, now),
+            expires: now,
+        });
+        let decision = entry.state.check(quota, now);
+        entry.expires = now + decision.reset;
+        decision
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().entries.len())
+            .sum()
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn check(&self, key: String, quota: Quota) -> BoxFuture<'static, Result<Decision, BoxError>> {
+        let decision = self.check_at(key, &quota, Instant::now());
+        Box::pin(std::future::ready(Ok(decision)))
+    }
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("shards", &self.shards.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{MemoryStore, SWEEP_INTERVAL};
+    use crate::rate_limit::Quota;
+
+    #[test]
+    fn sweep() {
+        let store = MemoryStore::with_shards(1);
+        let quota = Quota::token_bucket(10, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert!(store.check_at("a".into(), &quota, now).allowed);
+        assert_eq!(store.len(), 1);
+
+        let later = now + Duration::from_secs(1);
+        for _ in 1..SWEEP_INTERVAL {
+            store.check_at("b".into(), &quota, later);
+        }
+        assert_eq!(store.len(), 1);
+    }
+}
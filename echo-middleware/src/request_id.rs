@@ -0,0 +1,426 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use echo_core::http::header::HeaderName;
+use echo_core::http::{HeaderValue, Response};
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+thread_local! {
+    static CURRENT: RefCell<Option<RequestId>> = const { RefCell::new(None) };
+}
+
+/// 请求ID，保存在请求的扩展中。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(HeaderValue);
+
+impl RequestId {
+    pub fn as_str(&self) -> &str {
+        // 构造时已经确保是可见的ASCII字符。
+        self.0.to_str().unwrap()
+    }
+
+    pub fn header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+
+    /// 当前正在处理的请求的ID。
+    ///
+    /// 只在[`RequestIdMiddleware`]内层的服务被轮询期间、在同一线程上可用，
+    /// 例如内层的`CatchErrorMiddleware`的错误处理程序可以用它获取ID。
+    /// 处理程序中用`tokio::spawn`等方式创建的任务里不可用，需要时应先从请求扩展中取出ID。
+    /// 外层的错误处理程序应使用[`RequestIdError`]。
+    pub fn current() -> Option<RequestId> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    fn parse(value: &HeaderValue) -> Option<Self> {
+        let valid = !value.is_empty() && value.to_str().is_ok();
+        valid.then(|| RequestId(value.clone()))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 为请求分配ID。
+///
+/// 请求中已经带有ID时沿用该ID，否则生成一个新的ID，默认使用UUIDv4。
+/// ID以[`RequestId`]的形式保存在请求扩展中，并添加到响应头。
+/// 内部服务的错误包装为[`RequestIdError`]，外层的错误处理程序可以从中取出ID并添加到响应头，
+/// 原始错误通过`source()`获得；内层的错误处理程序可以通过[`RequestId::current`]获取ID。
+#[derive(Clone)]
+pub struct RequestIdMiddleware {
+    header: HeaderName,
+    make_id: Arc<dyn Fn() -> String + Send + Sync>,
+}
+
+impl Default for RequestIdMiddleware {
+    fn default() -> Self {
+        Self {
+            header: HeaderName::from_static("x-request-id"),
+            make_id: Arc::new(|| uuid::Uuid::new_v4().to_string()),
+        }
+    }
+}
+
+impl RequestIdMiddleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 读取和写入ID的头部，默认为`x-request-id`。
+    pub fn header_name(mut self, name: HeaderName) -> Self {
+        self.header = name;
+        self
+    }
+
+    /// 使用ULID作为ID。
+    #[cfg(feature = "request-id-ulid")]
+    pub fn ulid(self) -> Self {
+        self.make_request_id(|| ulid::Ulid::new().to_string())
+    }
+
+    /// 自定义ID的生成方式，生成的ID必须是非空的可见ASCII字符串，否则改用UUIDv4。
+    pub fn make_request_id<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.make_id = Arc::new(f);
+        self
+    }
+
+    fn generate(&self) -> RequestId {
+        let id = (self.make_id)();
+        HeaderValue::try_from(id)
+            .ok()
+            .as_ref()
+            .and_then(RequestId::parse)
+            .unwrap_or_else(|| {
+                let id = uuid::Uuid::new_v4().to_string();
+                RequestId(HeaderValue::try_from(id).unwrap())
+            })
+    }
+}
+
+impl fmt::Debug for RequestIdMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdMiddleware")
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+impl<S> Middleware<S> for RequestIdMiddleware {
+    type Service = RequestIdService<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        RequestIdService {
+            inner: service,
+            config: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+    config: RequestIdMiddleware,
+}
+
+impl<S, B, ResB> Service<Request<B>> for RequestIdService<S>
+where
+    S: Service<Request<B>, Response = Response<ResB>>,
+    S::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = RequestIdFuture<S::Future>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let id = request
+            .headers()
+            .get(&self.config.header)
+            .and_then(RequestId::parse)
+            .unwrap_or_else(|| self.config.generate());
+
+        request
+            .headers_mut()
+            .insert(self.config.header.clone(), id.0.clone());
+        request.extensions_mut().insert(id.clone());
+
+        RequestIdFuture {
+            fut: self.inner.call(request),
+            header: Some(self.config.header.clone()),
+            id: Some(id),
+        }
+    }
+}
+
+impl<S> fmt::Debug for RequestIdService<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdService")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pin_project! {
+    pub struct RequestIdFuture<F> {
+        #[pin]
+        fut: F,
+        header: Option<HeaderName>,
+        id: Option<RequestId>,
+    }
+}
+
+impl<F, B, E> Future for RequestIdFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    E: Into<BoxError>,
+{
+    type Output = Result<Response<B>, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = {
+            let _guard = Scope::enter(this.id.clone());
+            ready!(this.fut.poll(cx))
+        };
+        let header = this.header.take().expect("polled after completion");
+        let id = this.id.take().expect("polled after completion");
+
+        let mut response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                let inner = err.into();
+                return Poll::Ready(Err(Box::new(RequestIdError { id, inner })));
+            }
+        };
+        if !response.headers().contains_key(&header) {
+            response.headers_mut().insert(header, id.0);
+        }
+        Poll::Ready(Ok(response))
+    }
+}
+
+/// [`RequestIdService`]内部服务返回的错误，附带请求的ID。
+pub struct RequestIdError {
+    id: RequestId,
+    inner: BoxError,
+}
+
+impl RequestIdError {
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+
+    pub fn inner(&self) -> &BoxError {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> BoxError {
+        self.inner
+    }
+
+    /// 在错误及其`source()`链中查找请求的ID。
+    pub fn find<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a RequestId> {
+        let mut next = Some(err);
+        while let Some(err) = next {
+            if let Some(err) = err.downcast_ref::<RequestIdError>() {
+                return Some(&err.id);
+            }
+            next = err.source();
+        }
+        None
+    }
+}
+
+impl fmt::Debug for RequestIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdError")
+            .field("id", &self.id)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl fmt::Display for RequestIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl std::error::Error for RequestIdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.inner)
+    }
+}
+
+/// 轮询内部服务期间设置[`RequestId::current`]，结束时恢复之前的值。
+struct Scope(Option<RequestId>);
+
+impl Scope {
+    fn enter(id: Option<RequestId>) -> Self {
+        Scope(CURRENT.with(|current| current.replace(id)))
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+impl<F> fmt::Debug for RequestIdFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdFuture")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{BoxError, Request, Response};
+
+    use super::{RequestId, RequestIdError, RequestIdMiddleware};
+
+    async fn handler(request: Request) -> Result<Response, BoxError> {
+        let id = request.extensions().get::<RequestId>().unwrap();
+        assert_eq!(request.headers()["x-request-id"], id.as_str());
+        if request.uri().path() == "/error" {
+            return Err("failed".into());
+        }
+        Ok(Response::default())
+    }
+
+    fn request(uri: &str, id: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(id) = id {
+            builder = builder.header("x-request-id", id);
+        }
+        builder.body(Default::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn propagate() {
+        let service = service_fn(handler).with(RequestIdMiddleware::new());
+
+        let response = service.call(request("/", Some("abc"))).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "abc");
+
+        let response = service.call(request("/", None)).await.unwrap();
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 36);
+
+        let err = service
+            .call(request("/error", Some("abc")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "failed");
+        assert_eq!(RequestIdError::find(&*err).unwrap().as_str(), "abc");
+        assert!(RequestId::current().is_none());
+    }
+
+    #[cfg(feature = "core")]
+    #[tokio::test]
+    async fn error_handler() {
+        use std::convert::Infallible;
+
+        use echo_core::body::BodyExt;
+        use echo_core::http::StatusCode;
+        use echo_core::response::IntoResponse;
+        use echo_route::{RouteError, RouteErrorKind, Router};
+
+        use crate::core::CatchErrorMiddleware;
+
+        // 错误处理程序在最外层，从错误中取出ID。
+        async fn outer(err: BoxError) -> Result<Response, Infallible> {
+            let err = err.downcast::<RequestIdError>().unwrap();
+            let kind = err.inner().downcast_ref::<RouteError>().unwrap().kind();
+            assert_eq!(kind, RouteErrorKind::NotFound);
+            let id = err.id().clone();
+            let mut response = (StatusCode::NOT_FOUND, id.to_string()).into_response();
+            response
+                .headers_mut()
+                .insert("x-request-id", id.header_value().clone());
+            Ok(response)
+        }
+
+        // 错误处理程序在内层，通过`RequestId::current`获取ID。
+        async fn inner(err: BoxError) -> Result<Response, Infallible> {
+            let kind = err.downcast_ref::<RouteError>().unwrap().kind();
+            assert_eq!(kind, RouteErrorKind::NotFound);
+            let id = RequestId::current().unwrap();
+            Ok((StatusCode::NOT_FOUND, id.to_string()).into_response())
+        }
+
+        async fn check_not_found(response: Response) {
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            assert_eq!(response.headers()["x-request-id"], "abc");
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, "abc");
+        }
+
+        let service = Router::new().with(RequestIdMiddleware::new());
+        let err = service.call(request("/", Some("abc"))).await.unwrap_err();
+        let source = std::error::Error::source(&*err).unwrap();
+        assert!(source.is::<RouteError>());
+
+        let service = Router::new()
+            .with(RequestIdMiddleware::new())
+            .with(CatchErrorMiddleware::new(outer));
+        let response = service.call(request("/", Some("abc"))).await.unwrap();
+        check_not_found(response).await;
+
+        let service = Router::new()
+            .with(CatchErrorMiddleware::new(inner))
+            .with(RequestIdMiddleware::new());
+        let response = service.call(request("/", Some("abc"))).await.unwrap();
+        check_not_found(response).await;
+    }
+
+    #[tokio::test]
+    async fn custom() {
+        let service = service_fn(|request: Request| async move {
+            let id = request.extensions().get::<RequestId>().unwrap();
+            assert_eq!(id.as_str(), "fixed");
+            Ok::<Response, BoxError>(Response::default())
+        })
+        .with(
+            RequestIdMiddleware::new()
+                .header_name("x-trace-id".parse().unwrap())
+                .make_request_id(|| "fixed".to_string()),
+        );
+
+        let response = service.call(request("/", Some("abc"))).await.unwrap();
+        assert_eq!(response.headers()["x-trace-id"], "fixed");
+        assert!(!response.headers().contains_key("x-request-id"));
+    }
+
+    #[tokio::test]
+    async fn invalid_generator() {
+        let service = service_fn(handler)
+            .with(RequestIdMiddleware::new().make_request_id(|| "bad\nid".to_string()));
+
+        // 生成的ID无效时改用UUIDv4。
+        let response = service.call(request("/", None)).await.unwrap();
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 36);
+    }
+}
@@ -0,0 +1,90 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use echo_core::body::{Body, Bytes, Frame, SizeHint};
+use echo_core::BoxError;
+use pin_project_lite::pin_project;
+use tokio::time::{Instant, Sleep};
+
+use super::TimeoutError;
+
+pin_project! {
+    /// 读取超时的消息体，超时后返回[`TimeoutError`]。
+    pub struct TimeoutBody<B> {
+        #[pin]
+        inner: B,
+        #[pin]
+        timer: Sleep,
+        timeout: Duration,
+        idle: bool,
+        started: bool,
+    }
+}
+
+impl<B> TimeoutBody<B> {
+    /// 从第一次读取开始计时，必须在`timeout`内读取完整个消息体。
+    pub(crate) fn deadline(inner: B, timeout: Duration) -> Self {
+        Self::new(inner, timeout, false)
+    }
+
+    /// 每收到一帧后重新计时，两帧之间的间隔不能超过`timeout`。
+    pub(crate) fn idle(inner: B, timeout: Duration) -> Self {
+        Self::new(inner, timeout, true)
+    }
+
+    fn new(inner: B, timeout: Duration, idle: bool) -> Self {
+        Self {
+            inner,
+            timer: tokio::time::sleep(timeout),
+            timeout,
+            idle,
+            started: false,
+        }
+    }
+}
+
+impl<B> Body for TimeoutBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let mut this = self.project();
+        if !*this.started {
+            *this.started = true;
+            this.timer.as_mut().reset(Instant::now() + *this.timeout);
+        }
+
+        if let Poll::Ready(frame) = this.inner.poll_frame(cx) {
+            if *this.idle {
+                this.timer.reset(Instant::now() + *this.timeout);
+            }
+            return Poll::Ready(frame.map(|frame| frame.map_err(Into::into)));
+        }
+
+        match this.timer.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Some(Err(TimeoutError.into()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> std::fmt::Debug for TimeoutBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeoutBody")
+            .field("timeout", &self.timeout)
+            .field("idle", &self.idle)
+            .finish()
+    }
+}
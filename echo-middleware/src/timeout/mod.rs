@@ -0,0 +1,231 @@
+mod body;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use pin_project_lite::pin_project;
+use tokio::time::Sleep;
+
+pub use self::body::TimeoutBody;
+
+/// 超时中间件。
+///
+/// 内部服务没有在限定时间内返回响应时返回[`TimeoutError`]，
+/// 可以通过`CatchErrorMiddleware`将其转换为503或504响应。
+/// 读取请求体和发送响应体的超时分别通过[`request_body_timeout`]和[`response_body_timeout`]设置，
+/// 超时后读取消息体会返回[`TimeoutError`]。
+///
+/// [`request_body_timeout`]: TimeoutMiddleware::request_body_timeout
+/// [`response_body_timeout`]: TimeoutMiddleware::response_body_timeout
+#[derive(Debug, Clone, Copy)]
+pub struct TimeoutMiddleware {
+    timeout: Duration,
+    request_body: Option<Duration>,
+    response_body: Option<Duration>,
+}
+
+impl TimeoutMiddleware {
+    /// 等待响应的最长时间，不包括发送响应体的时间。
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            request_body: None,
+            response_body: None,
+        }
+    }
+
+    /// 读取完整个请求体的最长时间，从第一次读取开始计时。
+    pub fn request_body_timeout(mut self, timeout: Duration) -> Self {
+        self.request_body = Some(timeout);
+        self
+    }
+
+    /// 响应体两帧之间的最长间隔。
+    pub fn response_body_timeout(mut self, timeout: Duration) -> Self {
+        self.response_body = Some(timeout);
+        self
+    }
+}
+
+impl<S> Middleware<S> for TimeoutMiddleware {
+    type Service = Timeout<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        Timeout {
+            inner: service,
+            config: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Timeout<S> {
+    inner: S,
+    config: TimeoutMiddleware,
+}
+
+impl<S, B, ResB> Service<Request<B>> for Timeout<S>
+where
+    S: Service<Request, Response = echo_core::http::Response<ResB>>,
+    S::Error: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = BoxError;
+    type Future = TimeoutFuture<S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let request = request.map(|body| match self.config.request_body {
+            Some(timeout) => TimeoutBody::deadline(body, timeout).boxed(),
+            None => body.boxed(),
+        });
+        TimeoutFuture {
+            fut: self.inner.call(request),
+            timer: tokio::time::sleep(self.config.timeout),
+            response_body: self.config.response_body,
+        }
+    }
+}
+
+impl<S> fmt::Debug for Timeout<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+pin_project! {
+    pub struct TimeoutFuture<F> {
+        #[pin]
+        fut: F,
+        #[pin]
+        timer: Sleep,
+        response_body: Option<Duration>,
+    }
+}
+
+impl<F, B, E> Future for TimeoutFuture<F>
+where
+    F: Future<Output = Result<echo_core::http::Response<B>, E>>,
+    E: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if let Poll::Ready(result) = this.fut.poll(cx) {
+            let response = result.map_err(Into::into)?;
+            let response = response.map(|body| match this.response_body {
+                Some(timeout) => TimeoutBody::idle(body, *timeout).boxed(),
+                None => body.boxed(),
+            });
+            return Poll::Ready(Ok(response));
+        }
+
+        match this.timer.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(TimeoutError.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F> fmt::Debug for TimeoutFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeoutFuture").finish()
+    }
+}
+
+/// 请求处理、读取请求体或发送响应体超时。
+#[derive(Debug)]
+pub struct TimeoutError;
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation timed out")
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use echo_core::body::{Body, BodyExt, Bytes, Frame};
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{BoxError, Request, Response};
+
+    use super::{TimeoutError, TimeoutMiddleware};
+
+    /// 永远不会结束的消息体。
+    struct Pending;
+
+    impl Body for Pending {
+        type Error = BoxError;
+
+        fn poll_frame(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+            Poll::Pending
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn handler() {
+        let service = service_fn(|request: Request| async move {
+            let delay = request
+                .uri()
+                .path()
+                .trim_start_matches('/')
+                .parse()
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            Ok::<Response, BoxError>(Response::default())
+        })
+        .with(TimeoutMiddleware::new(Duration::from_secs(5)));
+
+        let request = |uri| Request::builder().uri(uri).body(()).unwrap();
+        assert!(service.call(request("/1")).await.is_ok());
+
+        let err = service.call(request("/10")).await.unwrap_err();
+        assert!(err.is::<TimeoutError>());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn body() {
+        let service = service_fn(|request: Request| async move {
+            let err = request.into_body().collect().bytes().await.unwrap_err();
+            assert!(err.is::<TimeoutError>());
+            Ok::<_, BoxError>(Response::new(Pending.boxed()))
+        })
+        .with(
+            TimeoutMiddleware::new(Duration::from_secs(5))
+                .request_body_timeout(Duration::from_secs(1))
+                .response_body_timeout(Duration::from_secs(1)),
+        );
+
+        let request = Request::builder().uri("/").body(Pending).unwrap();
+        let response = service.call(request).await.unwrap();
+        let err = response.into_body().collect().bytes().await.unwrap_err();
+        assert!(err.is::<TimeoutError>());
+    }
+}
@@ -0,0 +1,72 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use echo_core::body::{Body, Bytes, Frame, SizeHint};
+use echo_core::BoxError;
+use pin_project_lite::pin_project;
+use tracing::Span;
+
+pin_project! {
+    /// 在请求的span中读取消息体，并记录读取时的错误。
+    pub struct TraceBody<B> {
+        #[pin]
+        inner: B,
+        span: Span,
+        kind: &'static str,
+    }
+}
+
+impl<B> TraceBody<B> {
+    pub(crate) fn request(inner: B, span: Span) -> Self {
+        Self {
+            inner,
+            span,
+            kind: "request",
+        }
+    }
+
+    pub(crate) fn response(inner: B, span: Span) -> Self {
+        Self {
+            inner,
+            span,
+            kind: "response",
+        }
+    }
+}
+
+impl<B> Body for TraceBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let this = self.project();
+        let _guard = this.span.enter();
+        match this.inner.poll_frame(cx) {
+            Poll::Ready(Some(Err(err))) => {
+                let err = err.into();
+                tracing::error!(error = %err, "failed to read {} body", this.kind);
+                Poll::Ready(Some(Err(err)))
+            }
+            poll => poll.map_err(Into::into),
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> std::fmt::Debug for TraceBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceBody")
+            .field("span", &self.span)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
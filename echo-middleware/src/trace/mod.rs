@@ -0,0 +1,405 @@
+mod body;
+
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use echo_core::body::{Body, BodyExt};
+use echo_core::http::request::Parts;
+use echo_core::service::{Middleware, Service};
+use echo_core::{BoxError, Request, Response};
+use echo_route::RouteError;
+use pin_project_lite::pin_project;
+use tracing::field::Empty;
+use tracing::Span;
+
+pub use self::body::TraceBody;
+
+/// 为每个请求创建span。
+///
+/// 内部服务和消息体都在该span中执行，路由器匹配成功后会在当前span中记录`route`字段，
+/// 响应后记录`status`和`latency`字段，自定义span时需要声明这些字段才能记录。
+/// 内部服务返回的错误以及读取消息体时的错误会以`ERROR`级别记录，
+/// 其中[`RouteError`]通常由错误处理程序转换为4xx响应，以`DEBUG`级别记录。
+#[derive(Clone)]
+pub struct TraceMiddleware {
+    make_span: Arc<dyn Fn(&Parts) -> Span + Send + Sync>,
+}
+
+impl Default for TraceMiddleware {
+    fn default() -> Self {
+        Self {
+            make_span: Arc::new(|parts| {
+                tracing::info_span!(
+                    "request",
+                    method = %parts.method,
+                    uri = %parts.uri,
+                    version = ?parts.version,
+                    route = Empty,
+                    status = Empty,
+                    latency = Empty,
+                )
+            }),
+        }
+    }
+}
+
+impl TraceMiddleware {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 自定义请求的span。
+    pub fn make_span<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Parts) -> Span + Send + Sync + 'static,
+    {
+        self.make_span = Arc::new(f);
+        self
+    }
+}
+
+impl fmt::Debug for TraceMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceMiddleware").finish()
+    }
+}
+
+impl<S> Middleware<S> for TraceMiddleware {
+    type Service = Trace<S>;
+
+    fn transform(self, service: S) -> Self::Service {
+        Trace {
+            inner: service,
+            make_span: self.make_span,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Trace<S> {
+    inner: S,
+    make_span: Arc<dyn Fn(&Parts) -> Span + Send + Sync>,
+}
+
+impl<S, B, ResB> Service<Request<B>> for Trace<S>
+where
+    S: Service<Request, Response = echo_core::http::Response<ResB>>,
+    S::Error: fmt::Display + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+    ResB: Body + Send + 'static,
+    ResB::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = TraceFuture<S::Future>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let (parts, body) = request.into_parts();
+        let span = (self.make_span)(&parts);
+        let request = Request::from_parts(parts, TraceBody::request(body, span.clone()).boxed());
+
+        let fut = span.in_scope(|| {
+            tracing::debug!("started processing request");
+            self.inner.call(request)
+        });
+        TraceFuture {
+            fut,
+            span,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Trace<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Trace").field("inner", &self.inner).finish()
+    }
+}
+
+pin_project! {
+    pub struct TraceFuture<F> {
+        #[pin]
+        fut: F,
+        span: Span,
+        start: Instant,
+    }
+}
+
+impl<F, B, E> Future for TraceFuture<F>
+where
+    F: Future<Output = Result<echo_core::http::Response<B>, E>>,
+    E: fmt::Display + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _guard = this.span.enter();
+        let result = futures_core::ready!(this.fut.poll(cx));
+
+        let latency = this.start.elapsed();
+        this.span.record("latency", tracing::field::debug(latency));
+        let response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                if is_route_error(&err) {
+                    tracing::debug!(error = %err, ?latency, "failed to process request");
+                } else {
+                    tracing::error!(error = %err, ?latency, "failed to process request");
+                }
+                return Poll::Ready(Err(err));
+            }
+        };
+
+        let status = response.status();
+        this.span.record("status", status.as_u16());
+        if status.is_server_error() {
+            tracing::error!(
+                status = status.as_u16(),
+                ?latency,
+                "finished processing request"
+            );
+        } else {
+            tracing::debug!(
+                status = status.as_u16(),
+                ?latency,
+                "finished processing request"
+            );
+        }
+
+        let span = this.span.clone();
+        Poll::Ready(Ok(
+            response.map(|body| TraceBody::response(body, span).boxed())
+        ))
+    }
+}
+
+fn is_route_error<E: 'static>(err: &E) -> bool {
+    let err = err as &dyn Any;
+    err.is::<RouteError>()
+        || err
+            .downcast_ref::<BoxError>()
+            .is_some_and(|err| err.is::<RouteError>())
+}
+
+impl<F> fmt::Debug for TraceFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceFuture")
+            .field("span", &self.span)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::fmt;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+
+    use echo_core::body::{Body, BodyExt, Bytes, Frame};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{BoxError, Request, Response};
+    use echo_route::{get, Router};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+    use tracing_core::span::Current;
+
+    use super::TraceMiddleware;
+
+    /// 记录所有span字段和事件的订阅者。
+    #[derive(Clone, Default)]
+    struct Capture {
+        fields: Arc<Mutex<Vec<(String, String)>>>,
+        events: Arc<Mutex<Vec<(Level, String)>>>,
+        spans: Arc<Mutex<Vec<&'static Metadata<'static>>>>,
+        stack: Arc<Mutex<Vec<Id>>>,
+    }
+
+    struct Fields<'a>(&'a mut Vec<(String, String)>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push((field.name().to_owned(), format!("{value:?}")));
+        }
+    }
+
+    impl Capture {
+        fn field(&self, name: &str) -> Option<String> {
+            let fields = self.fields.lock().unwrap();
+            fields
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.clone())
+        }
+
+        fn events(&self, level: Level) -> Vec<String> {
+            let events = self.events.lock().unwrap();
+            events
+                .iter()
+                .filter(|(l, _)| *l == level)
+                .map(|(_, message)| message.clone())
+                .collect()
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut Fields(&mut self.fields.lock().unwrap()));
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata());
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut Fields(&mut self.fields.lock().unwrap()));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Vec::new();
+            event.record(&mut Fields(&mut fields));
+            let message = fields
+                .into_iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let level = *event.metadata().level();
+            self.events.lock().unwrap().push((level, message));
+        }
+
+        fn enter(&self, span: &Id) {
+            self.stack.lock().unwrap().push(span.clone());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
+
+        fn current_span(&self) -> Current {
+            match self.stack.lock().unwrap().last() {
+                Some(id) => {
+                    let metadata = self.spans.lock().unwrap()[id.into_u64() as usize - 1];
+                    Current::new(id.clone(), metadata)
+                }
+                None => Current::none(),
+            }
+        }
+    }
+
+    /// 读取时返回错误的消息体。
+    struct Broken;
+
+    impl Body for Broken {
+        type Error = BoxError;
+
+        fn poll_frame(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+            Poll::Ready(Some(Err("connection reset".into())))
+        }
+    }
+
+    #[tokio::test]
+    async fn trace() {
+        let spans = Arc::new(AtomicUsize::new(0));
+        let counter = spans.clone();
+        let trace = TraceMiddleware::new().make_span(move |parts| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tracing::info_span!("request", uri = %parts.uri)
+        });
+
+        let service = service_fn(|request: Request| async move {
+            if request.uri().path() == "/error" {
+                return Err(BoxError::from("boom"));
+            }
+            Ok("hello".into_response())
+        })
+        .with(trace);
+
+        let request: Request = Request::builder()
+            .uri("/")
+            .body(Default::default())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(&body[..], b"hello");
+
+        let request: Request = Request::builder()
+            .uri("/error")
+            .body(Default::default())
+            .unwrap();
+        let err = service.call(request).await.unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+        assert_eq!(spans.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn record() {
+        let capture = Capture::default();
+        let _default = tracing::subscriber::set_default(capture.clone());
+
+        let service = Router::new()
+            .route(
+                "/users/:id",
+                get(service_fn(|_: Request| async {
+                    Ok::<_, Infallible>(Response::new(Broken.boxed()))
+                })),
+            )
+            .with(TraceMiddleware::new());
+
+        let request: Request = Request::builder()
+            .uri("/users/1")
+            .body(Default::default())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert!(response.into_body().collect().bytes().await.is_err());
+
+        assert_eq!(capture.field("route").as_deref(), Some("\"/users/:id\""));
+        assert_eq!(capture.field("status").as_deref(), Some("200"));
+        assert!(capture.field("latency").is_some());
+        let errors = capture.events(Level::ERROR);
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].contains("failed to read response body"),
+            "{errors:?}"
+        );
+        assert!(errors[0].contains("connection reset"), "{errors:?}");
+
+        // 路由错误不以`ERROR`级别记录。
+        let request: Request = Request::builder()
+            .uri("/missing")
+            .body(Default::default())
+            .unwrap();
+        assert!(service.call(request).await.is_err());
+        assert_eq!(capture.events(Level::ERROR).len(), 1);
+        let debug = capture.events(Level::DEBUG);
+        assert!(
+            debug
+                .iter()
+                .any(|e| e.contains("failed to process request")),
+            "{debug:?}"
+        );
+    }
+}
@@ -127,7 +127,15 @@ pub enum MultipartError {
 
 impl MultipartError {
     fn from_multer(error: multer::Error) -> Self {
-        MultipartError::Other(error.into())
+        match error {
+            // 保留读取请求体时的原始错误，例如`LengthLimitError`。
+            // multer在读取流时可能将`StreamReadFailed`再包装一层，需要逐层展开。
+            multer::Error::StreamReadFailed(e) => match e.downcast::<multer::Error>() {
+                Ok(e) => Self::from_multer(*e),
+                Err(e) => MultipartError::Other(e),
+            },
+            error => MultipartError::Other(error.into()),
+        }
     }
 }
 
@@ -142,4 +150,11 @@ impl fmt::Display for MultipartError {
     }
 }
 
-impl std::error::Error for MultipartError {}
+impl std::error::Error for MultipartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MultipartError::UnsupportedContentType => None,
+            MultipartError::Other(e) => Some(&**e),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use echo_core::http::Method;
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::Service;
+use echo_core::{BoxError, Request, Response};
+
+use crate::future::RouteFuture;
+use crate::{IntoMethodRoute, Router, RouterError};
+
+/// 可在运行时修改路由表的路由器句柄。
+///
+/// 每次修改都基于当前路由表的副本进行，完成后原子地替换；已经开始处理的请求不受影响。
+#[derive(Clone, Default)]
+pub struct DynamicRouter {
+    table: Arc<RwLock<Arc<Router>>>,
+    writer: Arc<Mutex<()>>,
+}
+
+impl DynamicRouter {
+    pub fn new(router: Router) -> Self {
+        Self {
+            table: Arc::new(RwLock::new(Arc::new(router))),
+            writer: Default::default(),
+        }
+    }
+
+    /// 当前路由表的快照。
+    pub fn load(&self) -> Arc<Router> {
+        self.table.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, router: Router) {
+        let _writer = self.lock_writer();
+        *self.table.write().unwrap() = Arc::new(router);
+    }
+
+    /// 修改路由表，返回错误时路由表保持不变。
+    pub fn update<F>(&self, f: F) -> Result<(), RouterError>
+    where
+        F: FnOnce(Router) -> Result<Router, RouterError>,
+    {
+        let _writer = self.lock_writer();
+        let router = f(Router::clone(&self.load()))?;
+        *self.table.write().unwrap() = Arc::new(router);
+        Ok(())
+    }
+
+    pub fn add_route<S>(&self, path: &str, service: S) -> Result<(), RouterError>
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + Send + Sync + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+        <S::Service as Service<Request>>::Future: Send,
+    {
+        self.update(|router| router.try_route(path, service))
+    }
+
+    pub fn add_scope<S>(&self, path: &str, service: S) -> Result<(), RouterError>
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + Send + Sync + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+        <S::Service as Service<Request>>::Future: Send,
+    {
+        self.update(|router| router.try_scope(path, service))
+    }
+
+    /// 移除路径下的所有服务，路径不存在时返回`false`。
+    pub fn remove_route(&self, path: &str) -> bool {
+        self.remove(|router| router.remove_route(path, None))
+    }
+
+    /// 移除路径下指定HTTP方法的服务，该路径没有其他服务时一并移除路径。
+    pub fn remove_route_method(&self, path: &str, method: &Method) -> bool {
+        self.remove(|router| router.remove_route(path, Some(method)))
+    }
+
+    pub fn remove_scope(&self, path: &str) -> bool {
+        self.remove(|router| router.remove_scope(path))
+    }
+
+    /// 修改期间持有的锁，不保护任何数据，只保证修改依次进行。
+    /// 修改函数panic时锁会中毒，此时路由表并未被替换，可以继续使用。
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn remove<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut Router) -> bool,
+    {
+        let _writer = self.lock_writer();
+        let mut router = Router::clone(&self.load());
+        if !f(&mut router) {
+            return false;
+        }
+        *self.table.write().unwrap() = Arc::new(router);
+        true
+    }
+}
+
+impl fmt::Debug for DynamicRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicRouter").finish()
+    }
+}
+
+impl Service<Request> for DynamicRouter {
+    type Response = Response;
+    type Error = BoxError;
+    type Future = RouteFuture<BoxFuture<'static, Result<Response, BoxError>>>;
+
+    fn call(&self, request: Request) -> Self::Future {
+        self.load().call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::panic::AssertUnwindSafe;
+
+    use echo_core::http::Method;
+    use echo_core::service::{service_fn, Service};
+    use echo_core::Request;
+
+    use super::DynamicRouter;
+    use crate::{get, post, RouteError, RouteErrorKind, Router};
+
+    async fn hello(_: Request) -> Result<&'static str, Infallible> {
+        Ok("hello")
+    }
+
+    async fn call(router: &DynamicRouter, method: Method, path: &str) -> Option<RouteErrorKind> {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Default::default())
+            .unwrap();
+        match router.call(request).await {
+            Ok(_) => None,
+            Err(e) => Some(e.downcast_ref::<RouteError>().unwrap().kind()),
+        }
+    }
+
+    #[tokio::test]
+    async fn add_and_remove() {
+        let router = DynamicRouter::new(Router::new().route("/a", get(service_fn(hello))));
+
+        router.add_route("/b", get(service_fn(hello))).unwrap();
+        router.add_route("/b", post(service_fn(hello))).unwrap();
+        assert!(router.add_route("/b", get(service_fn(hello))).is_err());
+
+        assert_eq!(call(&router, Method::GET, "/a").await, None);
+        assert_eq!(call(&router, Method::POST, "/b").await, None);
+
+        assert!(router.remove_route_method("/b", &Method::POST));
+        assert_eq!(
+            call(&router, Method::POST, "/b").await,
+            Some(RouteErrorKind::MethodNotAllowed)
+        );
+
+        assert!(router.remove_route("/a"));
+        assert!(!router.remove_route("/a"));
+        assert_eq!(
+            call(&router, Method::GET, "/a").await,
+            Some(RouteErrorKind::NotFound)
+        );
+        assert_eq!(call(&router, Method::GET, "/b").await, None);
+    }
+
+    #[tokio::test]
+    async fn panic_in_update() {
+        let router = DynamicRouter::new(Router::new().route("/a", get(service_fn(hello))));
+
+        // 路径冲突时`route`会panic。
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            router.update(|r| Ok(r.route("/a", get(service_fn(hello)))))
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(call(&router, Method::GET, "/a").await, None);
+        router.add_route("/b", get(service_fn(hello))).unwrap();
+        assert!(router.remove_route("/a"));
+        assert_eq!(call(&router, Method::GET, "/b").await, None);
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::sync::Arc;
 
 use echo_core::Request;
 use sync_wrapper::SyncWrapper;
@@ -7,6 +8,9 @@ use sync_wrapper::SyncWrapper;
 pub enum RouteErrorKind {
     NotFound,
     MethodNotAllowed,
+    NotAcceptable,
+    UnsupportedMediaType,
+    InvalidEncoding,
 }
 
 #[derive(Debug)]
@@ -17,6 +21,13 @@ pub struct RouteError {
 
 impl RouteError {
     pub fn new(kind: RouteErrorKind, request: Request) -> Self {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            kind = ?kind,
+            method = %request.method(),
+            path = request.uri().path(),
+            "route error"
+        );
         Self {
             kind,
             request: SyncWrapper::new(request),
@@ -31,6 +42,18 @@ impl RouteError {
         Self::new(RouteErrorKind::MethodNotAllowed, request)
     }
 
+    pub fn not_acceptable(request: Request) -> Self {
+        Self::new(RouteErrorKind::NotAcceptable, request)
+    }
+
+    pub fn unsupported_media_type(request: Request) -> Self {
+        Self::new(RouteErrorKind::UnsupportedMediaType, request)
+    }
+
+    pub fn invalid_encoding(request: Request) -> Self {
+        Self::new(RouteErrorKind::InvalidEncoding, request)
+    }
+
     pub fn kind(&self) -> RouteErrorKind {
         self.kind
     }
@@ -49,6 +72,9 @@ impl fmt::Display for RouteError {
         match self.kind() {
             RouteErrorKind::NotFound { .. } => f.write_str("Not Found"),
             RouteErrorKind::MethodNotAllowed { .. } => f.write_str("Method Not Allowed"),
+            RouteErrorKind::NotAcceptable => f.write_str("Not Acceptable"),
+            RouteErrorKind::UnsupportedMediaType => f.write_str("Unsupported Media Type"),
+            RouteErrorKind::InvalidEncoding => f.write_str("Invalid Percent-Encoding In Path"),
         }
     }
 }
@@ -59,6 +85,8 @@ impl std::error::Error for RouteError {}
 pub enum RouterError {
     Conflict { path: String, message: String },
     InvalidPath { path: String, message: String },
+    ConflictHost { host: String, message: String },
+    InvalidHost { host: String, message: String },
     TooManyPath,
 }
 
@@ -86,9 +114,117 @@ impl fmt::Display for RouterError {
             RouterError::InvalidPath { path, message } => {
                 write!(f, "invalid path {path} ({message})")
             }
+            RouterError::ConflictHost { host, message } => {
+                write!(f, "conflict host {host} ({message})")
+            }
+            RouterError::InvalidHost { host, message } => {
+                write!(f, "invalid host {host} ({message})")
+            }
             RouterError::TooManyPath => f.write_str("too many path"),
         }
     }
 }
 
 impl std::error::Error for RouterError {}
+
+/// 一次注册失败，附带双方的注册来源（见[`Router::source`](crate::Router::source)）。
+#[derive(Debug)]
+pub struct RouterIssue {
+    error: RouterError,
+    source: Option<Arc<str>>,
+    previous: Option<Arc<str>>,
+}
+
+impl RouterIssue {
+    pub(crate) fn new(
+        error: RouterError,
+        source: Option<Arc<str>>,
+        previous: Option<Arc<str>>,
+    ) -> Self {
+        Self {
+            error,
+            source,
+            previous,
+        }
+    }
+
+    pub fn error(&self) -> &RouterError {
+        &self.error
+    }
+
+    /// 本次注册的位置，即[`Router::source`]设置的名称。
+    ///
+    /// [`Router::source`]: crate::Router::source
+    pub fn location(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// 与之冲突的已注册路由的位置。
+    pub fn previous_location(&self) -> Option<&str> {
+        self.previous.as_deref()
+    }
+
+    pub fn into_error(self) -> RouterError {
+        self.error
+    }
+}
+
+impl fmt::Display for RouterIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        match (self.location(), self.previous_location()) {
+            (Some(source), Some(previous)) => {
+                write!(f, " [registered by {source}, previously by {previous}]")
+            }
+            (Some(source), None) => write!(f, " [registered by {source}]"),
+            (None, Some(previous)) => write!(f, " [previously registered by {previous}]"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for RouterIssue {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// 合并路由时收集到的全部注册失败。
+#[derive(Debug, Default)]
+pub struct RouterReport {
+    issues: Vec<RouterIssue>,
+}
+
+impl RouterReport {
+    pub(crate) fn push(&mut self, issue: RouterIssue) {
+        self.issues.push(issue);
+    }
+
+    pub fn issues(&self) -> &[RouterIssue] {
+        &self.issues
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    pub fn into_issues(self) -> Vec<RouterIssue> {
+        self.issues
+    }
+}
+
+impl fmt::Display for RouterReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} route registration error(s)", self.issues.len())?;
+        for issue in self.issues.iter() {
+            write!(f, "\n  - {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RouterReport {}
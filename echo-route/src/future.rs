@@ -2,15 +2,20 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use echo_core::BoxError;
+use echo_core::http::Extensions;
+use echo_core::{BoxError, Response};
 use pin_project_lite::pin_project;
 
+use crate::MatchedPath;
+
 pin_project! {
     #[project = RouteFutureProj]
     pub enum RouteFuture<F> {
         Future {
             #[pin]
             fut: F,
+            matched_path: Option<MatchedPath>,
+            meta: Extensions,
         },
         Error {
             err: Option<BoxError>,
@@ -19,16 +24,37 @@ pin_project! {
     }
 }
 
-impl<F, R, E> Future for RouteFuture<F>
+impl<F, E> Future for RouteFuture<F>
 where
-    F: Future<Output = Result<R, E>>,
+    F: Future<Output = Result<Response, E>>,
     E: Into<BoxError>,
 {
-    type Output = Result<R, BoxError>;
+    type Output = Result<Response, BoxError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let output = match self.as_mut().project() {
-            RouteFutureProj::Future { fut } => fut.poll(cx).map_err(Into::into),
+            RouteFutureProj::Future {
+                fut,
+                matched_path,
+                meta,
+            } => {
+                fut.poll(cx).map(|result| {
+                    let mut response = result.map_err(Into::into)?;
+                    // 路由元数据不覆盖处理程序或内层路由器写入的同类型值。
+                    if !meta.is_empty() {
+                        let mut extensions = std::mem::take(meta);
+                        extensions.extend(std::mem::take(response.extensions_mut()));
+                        *response.extensions_mut() = extensions;
+                    }
+                    // 嵌套的路由器先写入完整的模板，外层不再覆盖。
+                    if let Some(matched_path) = matched_path.take() {
+                        if response.extensions().get::<MatchedPath>().is_none() {
+                            response.extensions_mut().insert(matched_path);
+                        }
+                    }
+                    Ok(response)
+                })
+            }
             RouteFutureProj::Error { err } => {
                 Poll::Ready(Err(err.take().expect("polled after completion")))
             }
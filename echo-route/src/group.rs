@@ -0,0 +1,337 @@
+use std::fmt;
+
+use echo_core::response::IntoResponse;
+use echo_core::service::{middleware_fn, ArcService, Middleware, RcService, Service};
+use echo_core::{BoxError, Request, Response};
+
+use crate::router::Endpoint;
+use crate::{IntoMethodRoute, LocalRouter, MethodRoute, Route, Router, RouterError};
+
+type GroupService = ArcService<Request, Response, BoxError>;
+
+type LocalGroupService = RcService<Request, Response, BoxError>;
+
+type GroupEndpoint<S> = Endpoint<MethodRoute<S>>;
+
+type Layer<S> = Box<dyn Fn(S) -> S>;
+
+/// 路由组，组内所有路由共享路径前缀和中间件。
+///
+/// 与[`Router::scope`]不同，组内的路由直接注册到外层路由器中，不会改写请求路径。
+pub struct Group<S = GroupService> {
+    prefix: String,
+    routes: Vec<(String, GroupEndpoint<S>)>,
+    layers: Vec<Layer<S>>,
+    error: Option<RouterError>,
+}
+
+/// [`LocalRouter`]使用的路由组，服务不需要`Send`和`Sync`。
+pub type LocalGroup = Group<LocalGroupService>;
+
+impl<S> Group<S> {
+    pub(crate) fn new(prefix: &str) -> Self {
+        let mut group = Self {
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            routes: Vec::new(),
+            layers: Vec::new(),
+            error: None,
+        };
+        if !prefix.starts_with('/') {
+            group.fail(prefix);
+        }
+        group
+    }
+
+    pub fn group<F>(mut self, prefix: &str, f: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        if !prefix.starts_with('/') {
+            self.fail(prefix);
+            return self;
+        }
+        match f(Group::new(&format!("{}{prefix}", self.prefix))).into_routes() {
+            Ok(routes) => self.routes.extend(routes),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    fn push(
+        mut self,
+        path: &str,
+        endpoint: fn(MethodRoute<S>) -> GroupEndpoint<S>,
+        service: MethodRoute<S>,
+    ) -> Self {
+        if !path.starts_with('/') {
+            self.fail(path);
+            return self;
+        }
+        self.routes
+            .push((format!("{}{path}", self.prefix), endpoint(service)));
+        self
+    }
+
+    fn fail(&mut self, path: &str) {
+        self.error.get_or_insert(RouterError::InvalidPath {
+            path: path.to_owned(),
+            message: "path must start with a `/`".to_owned(),
+        });
+    }
+
+    pub(crate) fn into_routes(self) -> Result<Vec<(String, GroupEndpoint<S>)>, RouterError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let layers = self.layers;
+        let apply = |service: S| layers.iter().fold(service, |s, layer| layer(s));
+
+        Ok(self
+            .routes
+            .into_iter()
+            .map(|(path, endpoint)| {
+                let endpoint = match endpoint {
+                    Endpoint::Route(s) => Endpoint::Route(s.with(middleware_fn(&apply))),
+                    Endpoint::Scope(s) => Endpoint::Scope(s.with(middleware_fn(&apply))),
+                };
+                (path, endpoint)
+            })
+            .collect())
+    }
+}
+
+impl Group {
+    /// 中间件作用于组内的所有路由，包括嵌套组中的路由，与调用顺序无关。
+    pub fn with<T>(mut self, middleware: T) -> Self
+    where
+        T: Middleware<GroupService> + Clone + 'static,
+        T::Service: Service<Request> + Send + Sync + 'static,
+        <T::Service as Service<Request>>::Response: IntoResponse,
+        <T::Service as Service<Request>>::Error: Into<BoxError>,
+        <T::Service as Service<Request>>::Future: Send,
+    {
+        self.layers.push(Box::new(move |service| {
+            Router::into_arc_service(middleware.clone().transform(service))
+        }));
+        self
+    }
+
+    pub fn route<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + Send + Sync + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+        <S::Service as Service<Request>>::Future: Send,
+    {
+        self.add(path, Endpoint::Route, service)
+    }
+
+    pub fn scope<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + Send + Sync + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+        <S::Service as Service<Request>>::Future: Send,
+    {
+        self.add(path, Endpoint::Scope, service)
+    }
+
+    pub fn mount<S>(self, route: impl Into<Route<S>>) -> Self
+    where
+        S: Service<Request> + Send + Sync + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+        S::Future: Send,
+    {
+        let (path, service) = route.into().into_parts();
+        self.route(&path, service)
+    }
+
+    fn add<S>(
+        self,
+        path: &str,
+        endpoint: fn(MethodRoute<GroupService>) -> GroupEndpoint<GroupService>,
+        service: S,
+    ) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + Send + Sync + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+        <S::Service as Service<Request>>::Future: Send,
+    {
+        let service = service
+            .into_method_route()
+            .with(middleware_fn(Router::into_arc_service));
+        self.push(path, endpoint, service)
+    }
+}
+
+impl LocalGroup {
+    /// 中间件作用于组内的所有路由，包括嵌套组中的路由，与调用顺序无关。
+    pub fn with<T>(mut self, middleware: T) -> Self
+    where
+        T: Middleware<LocalGroupService> + Clone + 'static,
+        T::Service: Service<Request> + 'static,
+        <T::Service as Service<Request>>::Response: IntoResponse,
+        <T::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.layers.push(Box::new(move |service| {
+            LocalRouter::into_rc_service(middleware.clone().transform(service))
+        }));
+        self
+    }
+
+    pub fn route<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.add(path, Endpoint::Route, service)
+    }
+
+    pub fn scope<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.add(path, Endpoint::Scope, service)
+    }
+
+    pub fn mount<S>(self, route: impl Into<Route<S>>) -> Self
+    where
+        S: Service<Request> + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+    {
+        let (path, service) = route.into().into_parts();
+        self.route(&path, service)
+    }
+
+    fn add<S>(
+        self,
+        path: &str,
+        endpoint: fn(MethodRoute<LocalGroupService>) -> GroupEndpoint<LocalGroupService>,
+        service: S,
+    ) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        let service = service
+            .into_method_route()
+            .with(middleware_fn(LocalRouter::into_rc_service));
+        self.push(path, endpoint, service)
+    }
+}
+
+impl<S> fmt::Debug for Group<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Group")
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use echo_core::http::{HeaderValue, StatusCode};
+    use echo_core::service::{middleware_fn, service_fn, Middleware, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use super::GroupService;
+    use crate::{get, RouteErrorKind, Router};
+
+    async fn ok(_: Request) -> Result<&'static str, Infallible> {
+        Ok("ok")
+    }
+
+    /// 在响应头`x-layer`中追加`name`，内层中间件先追加。
+    fn tag(name: &'static str) -> impl Middleware<GroupService, Service = GroupService> + Clone {
+        middleware_fn(move |service: GroupService| {
+            service
+                .map_response(move |mut response: Response| {
+                    let value = HeaderValue::from_static(name);
+                    response.headers_mut().append("x-layer", value);
+                    response
+                })
+                .boxed_arc()
+        })
+    }
+
+    async fn layers(router: &Router, uri: &str) -> Result<Vec<String>, RouteErrorKind> {
+        let request = Request::builder()
+            .uri(uri)
+            .body(Default::default())
+            .unwrap();
+        let response = router
+            .call(request)
+            .await
+            .map_err(|e| e.downcast_ref::<crate::RouteError>().unwrap().kind())?;
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(response
+            .headers()
+            .get_all("x-layer")
+            .iter()
+            .map(|v| v.to_str().unwrap().to_owned())
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn prefix() {
+        let router = Router::new().group("/api/", |group| {
+            group
+                .route("/users", get(service_fn(ok)))
+                .group("/v1", |group| group.route("/posts", get(service_fn(ok))))
+        });
+
+        assert!(layers(&router, "/api/users").await.is_ok());
+        assert!(layers(&router, "/api/v1/posts").await.is_ok());
+        assert_eq!(
+            layers(&router, "/users").await,
+            Err(RouteErrorKind::NotFound)
+        );
+        assert_eq!(
+            layers(&router, "/api//users").await,
+            Err(RouteErrorKind::NotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn layer_order() {
+        let router = Router::new()
+            .route("/public", get(service_fn(ok)))
+            .group("/admin", |group| {
+                group
+                    .route("/users", get(service_fn(ok)))
+                    .with(tag("a"))
+                    .group("/v1", |group| {
+                        group.route("/posts", get(service_fn(ok))).with(tag("c"))
+                    })
+                    .with(tag("b"))
+            });
+
+        assert_eq!(layers(&router, "/public").await, Ok(vec![]));
+        assert_eq!(
+            layers(&router, "/admin/users").await,
+            Ok(vec!["a".into(), "b".into()])
+        );
+        assert_eq!(
+            layers(&router, "/admin/v1/posts").await,
+            Ok(vec!["c".into(), "a".into(), "b".into()])
+        );
+    }
+}
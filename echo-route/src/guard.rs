@@ -0,0 +1,352 @@
+use std::fmt;
+use std::sync::Arc;
+
+use echo_core::http::header::{self, HeaderName, HeaderValue};
+use echo_core::Request;
+
+use crate::RouteErrorKind;
+
+/// 路由守卫，在HTTP方法之外进一步筛选请求。
+///
+/// 守卫不通过时返回的[`RouteErrorKind`]决定了最终的路由错误。
+pub trait Guard: Send + Sync + 'static {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind>;
+
+    fn and<G>(self, other: G) -> And<Self, G>
+    where
+        Self: Sized,
+        G: Guard,
+    {
+        And(self, other)
+    }
+
+    fn or<G>(self, other: G) -> Or<Self, G>
+    where
+        Self: Sized,
+        G: Guard,
+    {
+        Or(self, other)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct BoxGuard(Arc<dyn Guard>);
+
+impl BoxGuard {
+    pub(crate) fn new<G: Guard>(guard: G) -> Self {
+        Self(Arc::new(guard))
+    }
+
+    pub(crate) fn and(self, other: BoxGuard) -> Self {
+        Self::new(And(self, other))
+    }
+}
+
+impl Guard for BoxGuard {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        self.0.check(request)
+    }
+}
+
+impl fmt::Debug for BoxGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoxGuard").finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+
+impl<A: Guard, B: Guard> Guard for And<A, B> {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        self.0.check(request)?;
+        self.1.check(request)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(A, B);
+
+impl<A: Guard, B: Guard> Guard for Or<A, B> {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        match self.0.check(request) {
+            Ok(()) => Ok(()),
+            Err(kind) => self.1.check(request).map_err(|_| kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Not<G>(G);
+
+impl<G: Guard> Guard for Not<G> {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        match self.0.check(request) {
+            Ok(()) => Err(RouteErrorKind::NotFound),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+pub fn not<G: Guard>(guard: G) -> Not<G> {
+    Not(guard)
+}
+
+#[derive(Clone, Copy)]
+pub struct FromFn<F>(F);
+
+impl<F> Guard for FromFn<F>
+where
+    F: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        if (self.0)(request) {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::NotFound)
+        }
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn")
+            .field("f", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: Fn(&Request) -> bool + Send + Sync + 'static,
+{
+    FromFn(f)
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderExists(HeaderName);
+
+impl Guard for HeaderExists {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        if request.headers().contains_key(&self.0) {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::NotFound)
+        }
+    }
+}
+
+/// # Panics
+///
+/// If `name` isn't a valid [`HeaderName`].
+pub fn header_exists<N>(name: N) -> HeaderExists
+where
+    N: TryInto<HeaderName>,
+    N::Error: fmt::Debug,
+{
+    HeaderExists(name.try_into().expect("invalid header name"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Header(HeaderName, HeaderValue);
+
+impl Guard for Header {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        if request
+            .headers()
+            .get_all(&self.0)
+            .iter()
+            .any(|v| v == self.1)
+        {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::NotFound)
+        }
+    }
+}
+
+/// # Panics
+///
+/// If `name` isn't a valid [`HeaderName`] or `value` isn't a valid [`HeaderValue`].
+pub fn header<N, V>(name: N, value: V) -> Header
+where
+    N: TryInto<HeaderName>,
+    N::Error: fmt::Debug,
+    V: TryInto<HeaderValue>,
+    V::Error: fmt::Debug,
+{
+    Header(
+        name.try_into().expect("invalid header name"),
+        value.try_into().expect("invalid header value"),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentType(mime::Mime);
+
+impl Guard for ContentType {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        let matched = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<mime::Mime>().ok())
+            .filter(|m| mime_matches(&self.0, m))
+            .is_some();
+
+        if matched {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::UnsupportedMediaType)
+        }
+    }
+}
+
+/// 请求的`Content-Type`与`mime`匹配，`mime`可以使用通配符（如`application/*`）。
+///
+/// # Panics
+///
+/// If `mime` isn't a valid media type.
+pub fn content_type(mime: &str) -> ContentType {
+    ContentType(mime.parse().expect("invalid media type"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Accept(mime::Mime);
+
+impl Guard for Accept {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        // 没有`Accept`头表示接受任何类型。
+        if request.headers().get(header::ACCEPT).is_none() {
+            return Ok(());
+        }
+
+        // 取最具体的匹配范围的权重，`q=0`表示不接受，权重无效的范围被忽略。
+        let q = request
+            .headers()
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|v| v.trim().parse::<mime::Mime>().ok())
+            .filter(|m| mime_matches(m, &self.0))
+            .filter_map(|m| {
+                let q = match m.get_param("q") {
+                    Some(q) => q
+                        .as_str()
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|q| (0.0..=1.0).contains(q))?,
+                    None => 1.0,
+                };
+                let specificity =
+                    (m.type_() != mime::STAR) as u8 + (m.subtype() != mime::STAR) as u8;
+                Some((specificity, q))
+            })
+            .fold(
+                None,
+                |best: Option<(u8, f32)>, (specificity, q)| match best {
+                    Some((s, _)) if s > specificity => best,
+                    _ => Some((specificity, q)),
+                },
+            )
+            .map_or(0.0, |(_, q)| q);
+
+        if q > 0.0 {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::NotAcceptable)
+        }
+    }
+}
+
+/// 请求的`Accept`接受`mime`类型。
+///
+/// # Panics
+///
+/// If `mime` isn't a valid media type.
+pub fn accept(mime: &str) -> Accept {
+    Accept(mime.parse().expect("invalid media type"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Query(String, Option<String>);
+
+impl Guard for Query {
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        let query = request.uri().query().unwrap_or_default();
+        let matched = form_urlencoded::parse(query.as_bytes()).any(|(k, v)| {
+            k == self.0.as_str() && self.1.as_ref().is_none_or(|value| v == value.as_str())
+        });
+
+        if matched {
+            Ok(())
+        } else {
+            Err(RouteErrorKind::NotFound)
+        }
+    }
+}
+
+pub fn query_exists(name: impl Into<String>) -> Query {
+    Query(name.into(), None)
+}
+
+pub fn query(name: impl Into<String>, value: impl Into<String>) -> Query {
+    Query(name.into(), Some(value.into()))
+}
+
+pub(crate) fn mime_matches(pattern: &mime::Mime, mime: &mime::Mime) -> bool {
+    (pattern.type_() == mime::STAR || pattern.type_() == mime.type_())
+        && (pattern.subtype() == mime::STAR || pattern.subtype() == mime.subtype())
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::http::header;
+    use echo_core::Request;
+
+    use super::{accept, content_type, query, Guard};
+    use crate::RouteErrorKind;
+
+    fn request(name: header::HeaderName, value: &str) -> Request {
+        Request::builder()
+            .uri("/?v=2")
+            .header(name, value)
+            .body(Default::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn content_type_guard() {
+        let guard = content_type("application/json");
+        let req = request(header::CONTENT_TYPE, "application/json; charset=utf-8");
+        assert_eq!(guard.check(&req), Ok(()));
+        let req = request(header::CONTENT_TYPE, "text/plain");
+        assert_eq!(guard.check(&req), Err(RouteErrorKind::UnsupportedMediaType));
+    }
+
+    #[test]
+    fn accept_guard() {
+        let guard = accept("application/json");
+        let req = request(header::ACCEPT, "text/html, application/*;q=0.5");
+        assert_eq!(guard.check(&req), Ok(()));
+        let req = request(header::ACCEPT, "text/html, application/json;q=0");
+        assert_eq!(guard.check(&req), Err(RouteErrorKind::NotAcceptable));
+        let req = request(header::ACCEPT, "application/*, application/json;q=0.0");
+        assert_eq!(guard.check(&req), Err(RouteErrorKind::NotAcceptable));
+        let req = request(header::ACCEPT, "application/*;q=0, application/json;q=0.5");
+        assert_eq!(guard.check(&req), Ok(()));
+        let req = request(header::ACCEPT, "application/json;q=2, */*");
+        assert_eq!(guard.check(&req), Ok(()));
+    }
+
+    #[test]
+    fn combinators() {
+        let req = request(header::ACCEPT, "*/*");
+        assert_eq!(query("v", "2").and(accept("text/html")).check(&req), Ok(()));
+        assert_eq!(
+            query("v", "1").or(content_type("text/html")).check(&req),
+            Err(RouteErrorKind::NotFound)
+        );
+    }
+}
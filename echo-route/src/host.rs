@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use echo_core::http::header;
+use echo_core::response::IntoResponse;
+use echo_core::service::future::BoxFuture;
+use echo_core::service::{ArcService, Service};
+use echo_core::{BoxError, Request, Response};
+
+use crate::future::RouteFuture;
+use crate::{RouteError, Router, RouterError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Label {
+    Exact(String),
+    Param(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HostPattern {
+    wildcard: bool,
+    labels: Vec<Label>,
+}
+
+impl HostPattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.to_ascii_lowercase();
+
+        let (wildcard, rest) = match pattern.strip_prefix("*.") {
+            Some(rest) => (true, rest),
+            None => (false, pattern.as_str()),
+        };
+
+        if rest.is_empty() {
+            return Err("host must not be empty".to_owned());
+        }
+
+        let mut labels = Vec::new();
+
+        for label in rest.split('.') {
+            if label.is_empty() {
+                return Err("host must not contain empty labels".to_owned());
+            }
+            if label.contains('*') {
+                return Err("wildcard is only allowed as the leftmost label".to_owned());
+            }
+            if let Some(name) = label.strip_prefix(':') {
+                if name.is_empty() {
+                    return Err("capture must be named".to_owned());
+                }
+                labels.push(Label::Param(name.to_owned()));
+            } else {
+                labels.push(Label::Exact(label.to_owned()));
+            }
+        }
+
+        Ok(Self { wildcard, labels })
+    }
+
+    fn is_exact(&self) -> bool {
+        !self.wildcard && self.labels.iter().all(|l| matches!(l, Label::Exact(_)))
+    }
+
+    fn to_exact(&self) -> String {
+        self.labels
+            .iter()
+            .map(|l| match l {
+                Label::Exact(s) | Label::Param(s) => s.as_str(),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    fn matches(&self, host: &str) -> Option<Vec<(String, String)>> {
+        let labels = host.split('.').collect::<Vec<_>>();
+
+        let skip = if self.wildcard {
+            if labels.len() <= self.labels.len() {
+                return None;
+            }
+            labels.len() - self.labels.len()
+        } else {
+            if labels.len() != self.labels.len() {
+                return None;
+            }
+            0
+        };
+
+        let mut params = Vec::new();
+
+        for (pattern, label) in self.labels.iter().zip(&labels[skip..]) {
+            match pattern {
+                Label::Exact(s) if s == label => {}
+                Label::Param(name) if !label.is_empty() => {
+                    params.push((name.clone(), (*label).to_owned()));
+                }
+                _ => return None,
+            }
+        }
+
+        Some(params)
+    }
+}
+
+/// 根据请求的主机名分发到不同的服务。
+///
+/// 支持精确匹配（`example.com`）、捕获（`:tenant.example.com`）和通配子域名（`*.example.com`），
+/// 捕获的值通过[`PathParams`](crate::PathParams)提供。
+#[derive(Default)]
+pub struct HostRouter {
+    exact: HashMap<String, ArcService<Request, Response, BoxError>>,
+    patterns: Vec<(HostPattern, ArcService<Request, Response, BoxError>)>,
+    fallback: Option<ArcService<Request, Response, BoxError>>,
+}
+
+impl HostRouter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn host<S>(self, pattern: &str, service: S) -> Self
+    where
+        S: Service<Request> + Send + Sync + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+        S::Future: Send,
+    {
+        self.try_host(pattern, service).unwrap()
+    }
+
+    pub fn try_host<S>(mut self, pattern: &str, service: S) -> Result<Self, RouterError>
+    where
+        S: Service<Request> + Send + Sync + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+        S::Future: Send,
+    {
+        let parsed = HostPattern::parse(pattern).map_err(|message| RouterError::InvalidHost {
+            host: pattern.to_owned(),
+            message,
+        })?;
+
+        let conflict = || RouterError::ConflictHost {
+            host: pattern.to_owned(),
+            message: "conflict with previously registered host".to_owned(),
+        };
+
+        let service = Router::into_arc_service(service);
+
+        if parsed.is_exact() {
+            let host = parsed.to_exact();
+            if self.exact.contains_key(&host) {
+                return Err(conflict());
+            }
+            self.exact.insert(host, service);
+        } else {
+            if self.patterns.iter().any(|(p, _)| *p == parsed) {
+                return Err(conflict());
+            }
+            // 捕获优先于通配，同类按注册顺序匹配。
+            let index = if parsed.wildcard {
+                self.patterns.len()
+            } else {
+                self.patterns
+                    .iter()
+                    .position(|(p, _)| p.wildcard)
+                    .unwrap_or(self.patterns.len())
+            };
+            self.patterns.insert(index, (parsed, service));
+        }
+
+        Ok(self)
+    }
+
+    pub fn fallback<S>(mut self, service: S) -> Self
+    where
+        S: Service<Request> + Send + Sync + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+        S::Future: Send,
+    {
+        self.fallback = Some(Router::into_arc_service(service));
+        self
+    }
+}
+
+impl fmt::Debug for HostRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostRouter").finish()
+    }
+}
+
+impl Service<Request> for HostRouter {
+    type Response = Response;
+    type Error = BoxError;
+    type Future = RouteFuture<BoxFuture<'static, Result<Response, BoxError>>>;
+
+    fn call(&self, mut request: Request) -> Self::Future {
+        let host = request_host(&request).map(|host| host.to_ascii_lowercase());
+
+        let service = host.and_then(|host| {
+            if let Some(service) = self.exact.get(&host) {
+                return Some(service);
+            }
+            self.patterns.iter().find_map(|(pattern, service)| {
+                let params = pattern.matches(&host)?;
+                crate::params::insert_path_params(request.extensions_mut(), params.clone(), params);
+                Some(service)
+            })
+        });
+
+        match service.or(self.fallback.as_ref()) {
+            Some(service) => RouteFuture::Future {
+                fut: service.call(request),
+                matched_path: None,
+                meta: Default::default(),
+            },
+            None => RouteFuture::Error {
+                err: Some(RouteError::not_found(request).into()),
+            },
+        }
+    }
+}
+
+fn request_host(request: &Request) -> Option<&str> {
+    let host = match request.uri().authority() {
+        Some(authority) => authority.as_str(),
+        None => request.headers().get(header::HOST)?.to_str().ok()?,
+    };
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    Some(strip_port(host))
+}
+
+fn strip_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        host.find(']').map_or(host, |i| &host[..=i])
+    } else {
+        host.rsplit_once(':').map_or(host, |(host, _)| host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use echo_core::body::BodyExt;
+    use echo_core::service::{service_fn, ArcService, Service, ServiceExt};
+    use echo_core::Request;
+
+    use super::{strip_port, HostPattern, HostRouter};
+    use crate::{get, PathParams, RouteError, RouteErrorKind, Router};
+
+    fn reply(name: &'static str) -> ArcService<Request, String, Infallible> {
+        service_fn(move |request: Request| async move {
+            let params = request
+                .extensions()
+                .get::<PathParams>()
+                .map(|params| format!("{:?}", params.get_ref()))
+                .unwrap_or_default();
+            Ok::<_, Infallible>(format!("{name}{params}"))
+        })
+        .boxed_arc()
+    }
+
+    fn request(host: Option<&str>, uri: &str) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(host) = host {
+            builder = builder.header("host", host);
+        }
+        builder.body(Default::default()).unwrap()
+    }
+
+    async fn call(
+        router: &HostRouter,
+        host: Option<&str>,
+        uri: &str,
+    ) -> Result<String, RouteErrorKind> {
+        let response = router
+            .call(request(host, uri))
+            .await
+            .map_err(|e| e.downcast_ref::<RouteError>().unwrap().kind())?;
+        let body = response.into_body().collect().bytes().await.unwrap();
+        Ok(String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn matches(pattern: &str, host: &str) -> Option<Vec<(String, String)>> {
+        HostPattern::parse(pattern).unwrap().matches(host)
+    }
+
+    #[test]
+    fn exact() {
+        assert_eq!(matches("example.com", "example.com"), Some(vec![]));
+        assert_eq!(matches("example.com", "www.example.com"), None);
+    }
+
+    #[test]
+    fn wildcard() {
+        assert_eq!(matches("*.example.com", "a.example.com"), Some(vec![]));
+        assert_eq!(matches("*.example.com", "a.b.example.com"), Some(vec![]));
+        assert_eq!(matches("*.example.com", "example.com"), None);
+    }
+
+    #[test]
+    fn capture() {
+        assert_eq!(
+            matches(":tenant.example.com", "acme.example.com"),
+            Some(vec![("tenant".to_owned(), "acme".to_owned())])
+        );
+        assert_eq!(matches(":tenant.example.com", "a.b.example.com"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(HostPattern::parse("").is_err());
+        assert!(HostPattern::parse("a..com").is_err());
+        assert!(HostPattern::parse("a.*.com").is_err());
+        assert!(HostPattern::parse(":.com").is_err());
+    }
+
+    #[test]
+    fn port() {
+        assert_eq!(strip_port("example.com:8080"), "example.com");
+        assert_eq!(strip_port("[::1]:8080"), "[::1]");
+        assert_eq!(strip_port("example.com"), "example.com");
+    }
+
+    #[tokio::test]
+    async fn dispatch() {
+        let router = HostRouter::new()
+            .host("*.example.com", reply("wildcard"))
+            .host(":tenant.example.com", reply("tenant"))
+            .host("api.example.com", reply("exact"))
+            .host(
+                ":tenant.app.com",
+                Router::new().route("/users/:id", get(reply("users"))),
+            );
+
+        for (host, uri, expected) in [
+            ("api.example.com", "/", "exact"),
+            ("API.example.com:8080", "/", "exact"),
+            ("acme.example.com", "/", r#"tenant[("tenant", "acme")]"#),
+            ("a.b.example.com", "/", "wildcard[]"),
+            (
+                "acme.app.com",
+                "/users/1",
+                r#"users[("tenant", "acme"), ("id", "1")]"#,
+            ),
+        ] {
+            assert_eq!(
+                call(&router, Some(host), uri).await.unwrap(),
+                expected,
+                "{host}"
+            );
+        }
+
+        // 没有匹配的主机或没有`Host`头时为404。
+        assert_eq!(
+            call(&router, Some("example.org"), "/").await,
+            Err(RouteErrorKind::NotFound)
+        );
+        assert_eq!(
+            call(&router, None, "/").await,
+            Err(RouteErrorKind::NotFound)
+        );
+        // 绝对形式的URI优先于`Host`头。
+        assert_eq!(
+            call(&router, Some("example.org"), "http://api.example.com/")
+                .await
+                .unwrap(),
+            "exact"
+        );
+    }
+
+    #[tokio::test]
+    async fn fallback() {
+        let router = HostRouter::new()
+            .host("example.com", reply("exact"))
+            .fallback(reply("fallback"));
+
+        assert_eq!(
+            call(&router, Some("example.com"), "/").await.unwrap(),
+            "exact"
+        );
+        assert_eq!(
+            call(&router, Some("example.org"), "/").await.unwrap(),
+            "fallback"
+        );
+        assert_eq!(call(&router, None, "/").await.unwrap(), "fallback");
+    }
+}
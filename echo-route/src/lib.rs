@@ -1,18 +1,35 @@
 #![forbid(unsafe_code)]
 #![deny(missing_debug_implementations)]
 
+mod dynamic;
 mod error;
+mod group;
+mod host;
+mod local;
 mod method;
+mod negotiate;
+mod normalize;
 mod params;
+mod pattern;
 mod router;
+mod uri;
 mod util;
 
 pub mod future;
+pub mod guard;
 
-pub use error::{RouteError, RouteErrorKind, RouterError};
+pub use dynamic::DynamicRouter;
+pub use error::{RouteError, RouteErrorKind, RouterError, RouterIssue, RouterReport};
+pub use group::{Group, LocalGroup};
+pub use guard::Guard;
+pub use host::HostRouter;
+pub use local::LocalRouter;
 pub use method::{
     any, connect, delete, get, head, method, options, patch, post, put, trace, IntoMethodRoute,
     MethodRoute,
 };
-pub use params::PathParams;
+pub use negotiate::Negotiated;
+pub use normalize::TrailingSlash;
+pub use params::{PathParams, RawPathParams};
 pub use router::{Route, Router};
+pub use uri::{MatchedPath, NestedPath, OriginalUri};
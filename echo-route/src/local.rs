@@ -0,0 +1,310 @@
+use std::fmt;
+use std::sync::Arc;
+
+use echo_core::response::IntoResponse;
+use echo_core::service::future::LocalBoxFuture;
+use echo_core::service::{middleware_fn, RcService, Service, ServiceExt};
+use echo_core::{BoxError, Request, Response};
+
+use crate::future::RouteFuture;
+use crate::normalize::TrailingSlash;
+use crate::router::Endpoint;
+use crate::router::{Dispatch, RouterCore};
+use crate::{IntoMethodRoute, LocalGroup, Route, RouterError, RouterReport};
+
+/// 单线程运行时使用的[`Router`](crate::Router)，服务不需要`Send`和`Sync`，可以捕获`Rc`等状态。
+///
+/// 匹配规则与[`Router`](crate::Router)完全相同。
+#[derive(Clone, Default)]
+pub struct LocalRouter {
+    core: RouterCore<RcService<Request, Response, BoxError>>,
+}
+
+impl LocalRouter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.core.policy.trailing_slash = trailing_slash;
+        self
+    }
+
+    pub fn merge_slashes(mut self, enable: bool) -> Self {
+        self.core.policy.merge_slashes = enable;
+        self
+    }
+
+    pub fn resolve_dots(mut self, enable: bool) -> Self {
+        self.core.policy.resolve_dots = enable;
+        self
+    }
+
+    pub fn case_insensitive(self, enable: bool) -> Self {
+        self.try_case_insensitive(enable).unwrap()
+    }
+
+    pub fn try_case_insensitive(mut self, enable: bool) -> Result<Self, RouterError> {
+        self.core.set_case_insensitive(enable)?;
+        Ok(self)
+    }
+
+    pub fn source(mut self, source: impl Into<Arc<str>>) -> Self {
+        self.core.source = Some(source.into());
+        self
+    }
+
+    pub fn override_conflicts(mut self, enable: bool) -> Self {
+        self.core.override_conflicts = enable;
+        self
+    }
+
+    pub fn route<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.try_route(path, service).unwrap()
+    }
+
+    pub fn try_route<S>(mut self, path: &str, service: S) -> Result<Self, RouterError>
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.core.route(
+            path,
+            service
+                .into_method_route()
+                .with(middleware_fn(Self::into_rc_service)),
+        )?;
+        Ok(self)
+    }
+
+    pub fn scope<S>(self, path: &str, service: S) -> Self
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.try_scope(path, service).unwrap()
+    }
+
+    pub fn try_scope<S>(mut self, path: &str, service: S) -> Result<Self, RouterError>
+    where
+        S: IntoMethodRoute,
+        S::Service: Service<Request> + 'static,
+        <S::Service as Service<Request>>::Response: IntoResponse,
+        <S::Service as Service<Request>>::Error: Into<BoxError>,
+    {
+        self.core.scope(
+            path,
+            service
+                .into_method_route()
+                .with(middleware_fn(Self::into_rc_service)),
+        )?;
+        Ok(self)
+    }
+
+    pub fn mount<S>(self, route: impl Into<Route<S>>) -> Self
+    where
+        S: Service<Request> + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+    {
+        self.try_mount(route).unwrap()
+    }
+
+    pub fn try_mount<S>(self, route: impl Into<Route<S>>) -> Result<Self, RouterError>
+    where
+        S: Service<Request> + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+    {
+        let (path, service) = route.into().into_parts();
+        self.try_route(&path, service)
+    }
+
+    pub fn group<F>(self, prefix: &str, f: F) -> Self
+    where
+        F: FnOnce(LocalGroup) -> LocalGroup,
+    {
+        self.try_group(prefix, f).unwrap()
+    }
+
+    pub fn try_group<F>(mut self, prefix: &str, f: F) -> Result<Self, RouterError>
+    where
+        F: FnOnce(LocalGroup) -> LocalGroup,
+    {
+        for (path, endpoint) in f(LocalGroup::new(prefix)).into_routes()? {
+            self = match endpoint {
+                Endpoint::Route(service) => self.try_route(&path, service)?,
+                Endpoint::Scope(service) => self.try_scope(&path, service)?,
+            };
+        }
+        Ok(self)
+    }
+
+    pub fn merge(self, other: LocalRouter) -> Self {
+        self.try_merge_all(other)
+            .unwrap_or_else(|report| panic!("{report}"))
+    }
+
+    pub fn try_merge(mut self, other: LocalRouter) -> Result<Self, RouterError> {
+        self.core.merge(other.core)?;
+        Ok(self)
+    }
+
+    pub fn try_merge_all(mut self, other: LocalRouter) -> Result<Self, RouterReport> {
+        self.core.merge_all(other.core)?;
+        Ok(self)
+    }
+
+    /// 检查能否合并`other`，不修改任何一方。
+    pub fn validate_merge(&self, other: &LocalRouter) -> Result<(), RouterReport> {
+        self.clone().try_merge_all(other.clone()).map(|_| ())
+    }
+
+    pub(crate) fn into_rc_service<S>(service: S) -> RcService<Request, Response, BoxError>
+    where
+        S: Service<Request> + 'static,
+        S::Response: IntoResponse,
+        S::Error: Into<BoxError>,
+    {
+        crate::util::try_downcast(service).unwrap_or_else(|service| {
+            service
+                .map_response(IntoResponse::into_response)
+                .map_err(Into::into)
+                .boxed_rc()
+        })
+    }
+}
+
+impl fmt::Debug for LocalRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalRouter").finish()
+    }
+}
+
+impl Service<Request> for LocalRouter {
+    type Response = Response;
+    type Error = BoxError;
+    type Future = RouteFuture<LocalBoxFuture<'static, Result<Response, BoxError>>>;
+
+    fn call(&self, request: Request) -> Self::Future {
+        match self.core.dispatch(request) {
+            Dispatch::Found(service, request) => service.call(request),
+            Dispatch::Redirect(response) => RouteFuture::Future {
+                fut: Box::pin(std::future::ready(Ok(response))),
+                matched_path: None,
+                meta: Default::default(),
+            },
+            Dispatch::Error(err) => RouteFuture::Error {
+                err: Some(err.into()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::convert::Infallible;
+    use std::rc::Rc;
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::HeaderValue;
+    use echo_core::service::{middleware_fn, service_fn, RcService, Service, ServiceExt};
+    use echo_core::{BoxError, Request, Response};
+
+    use super::LocalRouter;
+    use crate::get;
+
+    fn request(uri: &str) -> Request {
+        Request::builder()
+            .uri(uri)
+            .body(Default::default())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rc_state() {
+        let hits = Rc::new(Cell::new(0));
+        let counter = {
+            let hits = hits.clone();
+            service_fn(move |_: Request| {
+                let hits = hits.clone();
+                async move {
+                    hits.set(hits.get() + 1);
+                    Ok::<_, Infallible>(hits.get().to_string())
+                }
+            })
+        };
+
+        let router = LocalRouter::new()
+            .route("/hits", get(counter.clone()))
+            .merge(
+                LocalRouter::new().scope("/api", LocalRouter::new().route("/hits", get(counter))),
+            );
+
+        let response = router.call(request("/hits")).await.unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "1");
+
+        let response = router.call(request("/api/hits")).await.unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(body, "2");
+
+        assert!(router.call(request("/missing")).await.is_err());
+        assert_eq!(hits.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn group() {
+        let state = Rc::new(String::from("local"));
+        let handler = {
+            let state = state.clone();
+            service_fn(move |_: Request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(state.to_string()) }
+            })
+        };
+        let tag = middleware_fn(|service: RcService<Request, Response, BoxError>| {
+            service
+                .map_response(|mut response: Response| {
+                    let value = HeaderValue::from_static("api");
+                    response.headers_mut().insert("x-group", value);
+                    response
+                })
+                .boxed_rc()
+        });
+
+        let router = LocalRouter::new().group("/api", |group| {
+            group
+                .route("/state", get(handler.clone()))
+                .group("/v1", |group| group.route("/state", get(handler)))
+                .with(tag)
+        });
+
+        for uri in ["/api/state", "/api/v1/state"] {
+            let response = router.call(request(uri)).await.unwrap();
+            assert_eq!(response.headers()["x-group"], "api");
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, "local");
+        }
+
+        let other = LocalRouter::new().route(
+            "/api/state",
+            get(service_fn(|_: Request| async {
+                Ok::<_, Infallible>("other")
+            })),
+        );
+        assert!(router.validate_merge(&other).is_err());
+        assert!(router.try_group("api", |group| group).is_err());
+    }
+}
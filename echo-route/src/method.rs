@@ -1,67 +1,285 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
 
-use echo_core::http::Method;
-use echo_core::service::future::BoxFuture;
+use echo_core::http::{Extensions, Method};
 use echo_core::service::{ArcService, Middleware, Service};
 use echo_core::{BoxError, Request, Response};
+use mime::Mime;
 
 use crate::future::RouteFuture;
-use crate::RouteError;
+use crate::guard::{BoxGuard, Guard};
+use crate::negotiate::{self, MediaRange, Negotiated};
+use crate::{MatchedPath, RouteError, RouteErrorKind};
 
-#[derive(Debug, Default)]
-pub struct MethodRouter {
-    map: HashMap<Method, ArcService<Request, Response, BoxError>>,
-    any: Option<ArcService<Request, Response, BoxError>>,
+#[derive(Debug, Clone)]
+struct Candidate<S> {
+    guard: Option<BoxGuard>,
+    negotiation: Negotiation,
+    service: S,
+    meta: Meta,
+    source: Option<Arc<str>>,
+}
+
+impl<S> Candidate<S> {
+    /// 带守卫或声明了媒体类型的候选服务只在条件满足时才会被选中。
+    fn is_conditional(&self) -> bool {
+        self.guard.is_some() || !self.negotiation.is_empty()
+    }
+
+    fn check(&self, request: &Request) -> Result<(), RouteErrorKind> {
+        if let Some(guard) = &self.guard {
+            guard.check(request)?;
+        }
+        let consumes = &self.negotiation.consumes;
+        if !consumes.is_empty() && !negotiate::content_type_matches(request.headers(), consumes) {
+            return Err(RouteErrorKind::UnsupportedMediaType);
+        }
+        Ok(())
+    }
+
+    /// 在声明的响应类型中选出`Accept`权重最高的一个，均不可接受时返回`None`。
+    fn negotiate(&self, accept: Option<&[MediaRange]>) -> Option<(&Mime, u16)> {
+        self.negotiation
+            .produces
+            .iter()
+            .map(|mime| (mime, negotiate::quality(accept, mime)))
+            .filter(|(_, q)| *q > 0)
+            .fold(None, |best, (mime, q)| match best {
+                Some((_, best_q)) if best_q >= q => best,
+                _ => Some((mime, q)),
+            })
+    }
+}
+
+/// 同一HTTP方法下的候选服务，有条件的在前，无条件的至多一个且始终在最后。
+#[derive(Debug, Clone)]
+struct Candidates<S>(Vec<Candidate<S>>);
+
+impl<S> Default for Candidates<S> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<S> Candidates<S> {
+    fn fallback(&self) -> Option<&Candidate<S>> {
+        self.0.last().filter(|c| !c.is_conditional())
+    }
+
+    fn has_fallback(&self) -> bool {
+        self.fallback().is_some()
+    }
+
+    fn remove_fallback(&mut self) {
+        if self.has_fallback() {
+            self.0.pop();
+        }
+    }
+
+    fn push(&mut self, candidate: Candidate<S>) {
+        if candidate.is_conditional() && self.has_fallback() {
+            self.0.insert(self.0.len() - 1, candidate);
+        } else {
+            self.0.push(candidate);
+        }
+    }
+}
+
+/// `S`为装箱后的服务类型，[`Router`](crate::Router)使用[`ArcService`]，
+/// [`LocalRouter`](crate::LocalRouter)使用[`RcService`](echo_core::service::RcService)。
+#[derive(Debug, Clone)]
+pub struct MethodRouter<S = ArcService<Request, Response, BoxError>> {
+    map: HashMap<Method, Candidates<S>>,
+    any: Candidates<S>,
 }
 
-impl MethodRouter {
-    pub fn add(&mut self, service: ArcService<Request, Response, BoxError>, method: Method) {
-        self.map.insert(method, service);
+/// 合并时发现的冲突，`source`和`previous`分别为双方的注册来源。
+#[derive(Debug)]
+pub(crate) struct MethodConflict {
+    pub method: Option<Method>,
+    pub source: Option<Arc<str>>,
+    pub previous: Option<Arc<str>>,
+}
+
+impl<S> Default for MethodRouter<S> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            any: Candidates::default(),
+        }
+    }
+}
+
+impl<S> MethodRouter<S> {
+    pub fn remove(&mut self, method: &Method) -> bool {
+        self.map.remove(method).is_some()
     }
 
-    pub fn add_any(&mut self, service: ArcService<Request, Response, BoxError>) {
-        self.any = Some(service);
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty() && self.any.0.is_empty()
     }
 
-    pub fn contains(&self, method: &Method) -> bool {
-        self.map.contains_key(method)
+    /// 第一个候选服务的注册来源。
+    pub fn source(&self) -> Option<Arc<str>> {
+        self.any
+            .0
+            .iter()
+            .chain(self.map.values().flat_map(|candidates| candidates.0.iter()))
+            .find_map(|candidate| candidate.source.clone())
     }
 
-    pub fn contains_any(&self) -> bool {
-        self.any.is_some()
+    /// 替换所有候选服务，守卫、媒体类型和元数据保持不变。
+    pub(crate) fn map_services<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(S) -> S,
+        S: Clone,
+    {
+        let candidates = self
+            .map
+            .values_mut()
+            .flat_map(|candidates| candidates.0.iter_mut())
+            .chain(self.any.0.iter_mut());
+        for candidate in candidates {
+            candidate.service = f(candidate.service.clone());
+        }
+    }
+
+    /// 合并另一个`MethodRouter`，`replace`为真时后注册的不带守卫的服务覆盖先注册的。
+    ///
+    /// 存在冲突时不做任何修改。
+    pub fn merge(&mut self, other: MethodRouter<S>, replace: bool) -> Result<(), MethodConflict> {
+        let candidates = other
+            .any
+            .0
+            .into_iter()
+            .map(|candidate| (None, candidate))
+            .chain(other.map.into_iter().flat_map(|(method, candidates)| {
+                candidates
+                    .0
+                    .into_iter()
+                    .map(move |candidate| (Some(method.clone()), candidate))
+            }))
+            .collect::<Vec<_>>();
+
+        if !replace {
+            for (method, candidate) in candidates.iter() {
+                self.check(method.as_ref(), candidate)?;
+            }
+        }
+
+        for (method, candidate) in candidates {
+            let candidates = match method {
+                Some(method) => self.map.entry(method).or_default(),
+                None => &mut self.any,
+            };
+            if !candidate.is_conditional() {
+                candidates.remove_fallback();
+            }
+            candidates.push(candidate);
+        }
+
+        Ok(())
+    }
+
+    fn check(
+        &self,
+        method: Option<&Method>,
+        candidate: &Candidate<S>,
+    ) -> Result<(), MethodConflict> {
+        if candidate.is_conditional() {
+            return Ok(());
+        }
+        let candidates = match method {
+            Some(method) => self.map.get(method),
+            None => Some(&self.any),
+        };
+        match candidates.and_then(Candidates::fallback) {
+            Some(previous) => Err(MethodConflict {
+                method: method.cloned(),
+                source: candidate.source.clone(),
+                previous: previous.source.clone(),
+            }),
+            None => Ok(()),
+        }
     }
 }
 
-impl Service<Request> for MethodRouter {
+impl<S> Service<Request> for MethodRouter<S>
+where
+    S: Service<Request, Response = Response, Error = BoxError>,
+{
     type Response = Response;
     type Error = BoxError;
-    type Future = RouteFuture<BoxFuture<'static, Result<Response, BoxError>>>;
-
-    fn call(&self, request: Request) -> Self::Future {
-        pub fn match_<'a>(
-            router: &'a MethodRouter,
-            method: &Method,
-        ) -> Option<&'a ArcService<Request, Response, BoxError>> {
-            router
-                .map
-                .get(method)
-                .or_else(|| {
-                    if method == Method::HEAD {
-                        router.map.get(&Method::GET)
-                    } else {
-                        None
+    type Future = RouteFuture<S::Future>;
+
+    fn call(&self, mut request: Request) -> Self::Future {
+        let method = request.method();
+
+        let candidates = self
+            .map
+            .get(method)
+            .or_else(|| {
+                if method == Method::HEAD {
+                    self.map.get(&Method::GET)
+                } else {
+                    None
+                }
+            })
+            .into_iter()
+            .flat_map(|candidates| candidates.0.iter())
+            .chain(self.any.0.iter());
+
+        let accept = negotiate::parse_accept(request.headers());
+
+        // 没有候选服务时为405；条件均不满足时，优先报告比404更具体的错误。
+        let mut error = None;
+        // 声明了响应类型的候选服务按`Accept`权重选择，优先于未声明的；权重相同时按注册顺序。
+        let mut chosen: Option<(&Candidate<S>, Option<&Mime>, u16)> = None;
+
+        for candidate in candidates {
+            let result = candidate.check(&request).and_then(|()| {
+                if candidate.negotiation.produces.is_empty() {
+                    return Ok((None, 0));
+                }
+                match candidate.negotiate(accept.as_deref()) {
+                    Some((mime, q)) => Ok((Some(mime), q)),
+                    None => Err(RouteErrorKind::NotAcceptable),
+                }
+            });
+            match result {
+                Ok((mime, rank)) => {
+                    if chosen.is_none_or(|(_, _, r)| rank > r) {
+                        chosen = Some((candidate, mime, rank));
+                    }
+                }
+                Err(kind) => {
+                    if error.is_none_or(|e| e == RouteErrorKind::NotFound) {
+                        error = Some(kind);
                     }
-                })
-                .or_else(|| router.any.as_ref())
+                }
+            }
+        }
+
+        if let Some((candidate, mime, _)) = chosen {
+            if let Some(mime) = mime {
+                request.extensions_mut().insert(Negotiated(mime.clone()));
+            }
+            candidate.meta.insert_into(request.extensions_mut());
+            let mut meta = Extensions::new();
+            candidate.meta.insert_into(&mut meta);
+            let matched_path = request.extensions().get::<MatchedPath>().cloned();
+            return RouteFuture::Future {
+                fut: candidate.service.call(request),
+                matched_path,
+                meta,
+            };
         }
 
-        match match_(self, request.method()) {
-            Some(service) => RouteFuture::Future {
-                fut: service.call(request),
-            },
-            None => RouteFuture::Error {
-                err: Some(RouteError::method_not_allowed(request).into()),
-            },
+        let kind = error.unwrap_or(RouteErrorKind::MethodNotAllowed);
+
+        RouteFuture::Error {
+            err: Some(RouteError::new(kind, request).into()),
         }
     }
 }
@@ -86,9 +304,54 @@ impl Methods {
     }
 }
 
+/// 候选服务接受的请求类型和产生的响应类型。
+#[derive(Debug, Clone, Default)]
+struct Negotiation {
+    consumes: Vec<Mime>,
+    produces: Vec<Mime>,
+}
+
+impl Negotiation {
+    fn is_empty(&self) -> bool {
+        self.consumes.is_empty() && self.produces.is_empty()
+    }
+}
+
+/// 路由元数据，匹配成功后每一项都会被克隆并插入请求和响应的扩展中。
+#[derive(Clone, Default)]
+struct Meta(Vec<InsertFn>);
+
+type InsertFn = Arc<dyn Fn(&mut Extensions) + Send + Sync>;
+
+impl Meta {
+    fn insert<T>(&mut self, value: T)
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.0.push(Arc::new(move |extensions: &mut Extensions| {
+            extensions.insert(value.clone());
+        }));
+    }
+
+    fn insert_into(&self, extensions: &mut Extensions) {
+        for f in self.0.iter() {
+            f(extensions);
+        }
+    }
+}
+
+impl fmt::Debug for Meta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Meta").field(&self.0.len()).finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MethodRoute<S> {
     methods: Methods,
+    guard: Option<BoxGuard>,
+    negotiation: Negotiation,
+    meta: Meta,
     service: S,
 }
 
@@ -96,6 +359,9 @@ impl<S> MethodRoute<S> {
     pub fn any(service: S) -> Self {
         Self {
             methods: Methods::Any,
+            guard: None,
+            negotiation: Negotiation::default(),
+            meta: Meta::default(),
             service,
         }
     }
@@ -103,6 +369,9 @@ impl<S> MethodRoute<S> {
     pub fn one(service: S, method: Method) -> Self {
         Self {
             methods: Methods::One(method),
+            guard: None,
+            negotiation: Negotiation::default(),
+            meta: Meta::default(),
             service,
         }
     }
@@ -110,6 +379,9 @@ impl<S> MethodRoute<S> {
     pub fn more(service: S, methods: HashSet<Method>) -> Self {
         Self {
             methods: Methods::More(methods),
+            guard: None,
+            negotiation: Negotiation::default(),
+            meta: Meta::default(),
             service,
         }
     }
@@ -119,12 +391,62 @@ impl<S> MethodRoute<S> {
         self
     }
 
+    pub fn guard<G: Guard>(mut self, guard: G) -> Self {
+        let guard = BoxGuard::new(guard);
+        self.guard = Some(match self.guard {
+            Some(prev) => prev.and(guard),
+            None => guard,
+        });
+        self
+    }
+
+    /// 声明产生的响应类型，根据请求的`Accept`（支持权重和通配符）在同一路径和方法的
+    /// 多个服务中选择，选中的类型通过[`Negotiated`]提供；均不可接受时为406。
+    ///
+    /// # Panics
+    ///
+    /// If `mime` isn't a valid media type.
+    pub fn produces(mut self, mime: &str) -> Self {
+        self.negotiation
+            .produces
+            .push(mime.parse().expect("invalid media type"));
+        self
+    }
+
+    /// 声明接受的请求类型，可以使用通配符；请求的`Content-Type`均不匹配时为415。
+    ///
+    /// # Panics
+    ///
+    /// If `mime` isn't a valid media type.
+    pub fn consumes(mut self, mime: &str) -> Self {
+        self.negotiation
+            .consumes
+            .push(mime.parse().expect("invalid media type"));
+        self
+    }
+
+    /// 附加路由元数据。
+    ///
+    /// 匹配成功后插入请求的扩展中，供处理程序、路由上的中间件和
+    /// [`Router::route_layer`](crate::Router::route_layer)添加的中间件在处理程序之前读取；
+    /// 处理程序成功返回时也插入响应的扩展中，处理程序写入的同类型值优先。
+    pub fn meta<T>(mut self, value: T) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.meta.insert(value);
+        self
+    }
+
     pub fn with<T>(self, middleware: T) -> MethodRoute<T::Service>
     where
         T: Middleware<S>,
     {
         MethodRoute {
             methods: self.methods,
+            guard: self.guard,
+            negotiation: self.negotiation,
+            meta: self.meta,
             service: middleware.transform(self.service),
         }
     }
@@ -181,56 +503,29 @@ impl<S> IntoMethodRoute for MethodRoute<S> {
     }
 }
 
-pub trait MergeToMethodRouter {
-    fn merge_to(self, router: &mut MethodRouter) -> Result<(), Option<Method>>;
-}
-
-impl MergeToMethodRouter for MethodRouter {
-    fn merge_to(self, router: &mut MethodRouter) -> Result<(), Option<Method>> {
-        for method in self.map.keys() {
-            if router.contains(method) {
-                return Err(Some(method.clone()));
-            }
-        }
-        if let Some(service) = self.any {
-            if router.contains_any() {
-                return Err(None);
-            }
-            router.add_any(service);
-        }
-        for (method, service) in self.map {
-            router.add(service, method);
-        }
-        Ok(())
-    }
-}
-
-impl MergeToMethodRouter for MethodRoute<ArcService<Request, Response, BoxError>> {
-    fn merge_to(self, router: &mut MethodRouter) -> Result<(), Option<Method>> {
+impl<S: Clone> MethodRoute<S> {
+    pub(crate) fn into_method_router(self, source: Option<Arc<str>>) -> MethodRouter<S> {
+        let mut router = MethodRouter::default();
+        let candidate = Candidate {
+            guard: self.guard,
+            negotiation: self.negotiation,
+            service: self.service,
+            meta: self.meta,
+            source,
+        };
         match self.methods {
-            Methods::Any => {
-                if router.contains_any() {
-                    return Err(None);
-                }
-                router.add_any(self.service);
-            }
-            Methods::One(method) => {
-                if router.contains(&method) {
-                    return Err(Some(method));
-                }
-                router.add(self.service, method);
-            }
+            Methods::Any => router.any.push(candidate),
+            Methods::One(method) => router.map.entry(method).or_default().push(candidate),
             Methods::More(methods) => {
-                for method in methods.iter() {
-                    if router.contains(&method) {
-                        return Err(Some(method.clone()));
-                    }
-                }
                 for method in methods {
-                    router.add(self.service.clone(), method);
+                    router
+                        .map
+                        .entry(method)
+                        .or_default()
+                        .push(candidate.clone());
                 }
             }
         }
-        Ok(())
+        router
     }
 }
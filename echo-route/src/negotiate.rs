@@ -0,0 +1,116 @@
+use echo_core::http::{header, HeaderMap};
+use echo_core::parse_quality;
+use mime::Mime;
+
+use crate::guard::mime_matches;
+
+/// 内容协商选中的响应媒体类型，见[`MethodRoute::produces`](crate::MethodRoute::produces)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated(pub Mime);
+
+/// `Accept`中的一个媒体范围，权重以千分之一为单位。
+#[derive(Debug)]
+pub(crate) struct MediaRange {
+    mime: Mime,
+    q: u16,
+}
+
+impl MediaRange {
+    fn matches(&self, mime: &Mime) -> bool {
+        mime_matches(&self.mime, mime)
+            && self
+                .mime
+                .params()
+                .filter(|(k, _)| *k != "q")
+                .all(|(k, v)| mime.get_param(k) == Some(v))
+    }
+
+    /// 越具体的范围优先级越高：带参数 > `type/subtype` > `type/*` > `*/*`。
+    fn specificity(&self) -> usize {
+        let params = self.mime.params().filter(|(k, _)| *k != "q").count();
+        (self.mime.type_() != mime::STAR) as usize
+            + (self.mime.subtype() != mime::STAR) as usize
+            + (params > 0) as usize
+    }
+}
+
+/// 解析`Accept`头，没有该头时返回`None`，表示接受任何类型。
+pub(crate) fn parse_accept(headers: &HeaderMap) -> Option<Vec<MediaRange>> {
+    headers.get(header::ACCEPT)?;
+
+    let ranges = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|v| v.trim().parse::<Mime>().ok())
+        .filter_map(|mime| {
+            let q = match mime.get_param("q") {
+                Some(q) => parse_quality(q.as_str())?,
+                None => 1000,
+            };
+            Some(MediaRange { mime, q })
+        })
+        .collect();
+
+    Some(ranges)
+}
+
+/// `mime`的权重，取最具体的匹配范围的权重，没有匹配时为0。
+pub(crate) fn quality(ranges: Option<&[MediaRange]>, mime: &Mime) -> u16 {
+    let Some(ranges) = ranges else {
+        return 1000;
+    };
+    ranges
+        .iter()
+        .filter(|range| range.matches(mime))
+        .max_by_key(|range| range.specificity())
+        .map_or(0, |range| range.q)
+}
+
+/// 请求的`Content-Type`与`consumes`中的任一类型匹配。
+pub(crate) fn content_type_matches(headers: &HeaderMap, consumes: &[Mime]) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<Mime>().ok())
+        .is_some_and(|content_type| consumes.iter().any(|m| mime_matches(m, &content_type)))
+}
+
+#[cfg(test)]
+mod tests {
+    use echo_core::http::{header, HeaderMap, HeaderValue};
+
+    use super::{parse_accept, quality};
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn quality_by_specificity() {
+        let headers = accept("application/*;q=0.5, application/vnd.acme.v2+json, */*;q=0.1");
+        let ranges = parse_accept(&headers);
+        let ranges = ranges.as_deref();
+
+        let q = |mime: &str| quality(ranges, &mime.parse().unwrap());
+        assert_eq!(q("application/vnd.acme.v2+json"), 1000);
+        assert_eq!(q("application/json"), 500);
+        assert_eq!(q("text/html"), 100);
+        assert_eq!(quality(None, &mime::TEXT_HTML), 1000);
+    }
+
+    #[test]
+    fn params_and_zero() {
+        let headers = accept("application/json; version=2, text/html;q=0");
+        let ranges = parse_accept(&headers);
+        let ranges = ranges.as_deref();
+
+        let q = |mime: &str| quality(ranges, &mime.parse().unwrap());
+        assert_eq!(q("application/json; version=2"), 1000);
+        assert_eq!(q("application/json; version=1"), 0);
+        assert_eq!(q("text/html"), 0);
+    }
+}
@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+use echo_core::http::StatusCode;
+
+/// 请求路径末尾`/`的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TrailingSlash {
+    /// `/users`与`/users/`是不同的路径。
+    #[default]
+    Strict,
+    /// 匹配失败时，添加或去除末尾的`/`后再次匹配。
+    Ignore,
+    /// 匹配失败时，若添加或去除末尾的`/`后能够匹配，以`301`重定向到该路径。
+    MovedPermanently,
+    /// 同[`TrailingSlash::MovedPermanently`]，但使用`308`保留请求方法和请求体。
+    PermanentRedirect,
+}
+
+impl TrailingSlash {
+    pub(crate) fn redirect_status(&self) -> Option<StatusCode> {
+        match self {
+            TrailingSlash::Strict | TrailingSlash::Ignore => None,
+            TrailingSlash::MovedPermanently => Some(StatusCode::MOVED_PERMANENTLY),
+            TrailingSlash::PermanentRedirect => Some(StatusCode::PERMANENT_REDIRECT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PathPolicy {
+    pub trailing_slash: TrailingSlash,
+    pub merge_slashes: bool,
+    pub resolve_dots: bool,
+}
+
+impl PathPolicy {
+    /// 返回规范化后的路径，路径未改变时返回`None`。
+    pub fn normalize(&self, path: &str) -> Option<String> {
+        let mut normalized = Cow::Borrowed(path);
+        if self.merge_slashes {
+            if let Cow::Owned(path) = merge_slashes(&normalized) {
+                normalized = Cow::Owned(path);
+            }
+        }
+        if self.resolve_dots {
+            if let Cow::Owned(path) = resolve_dots(&normalized) {
+                normalized = Cow::Owned(path);
+            }
+        }
+        match normalized {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(path) => Some(path),
+        }
+    }
+}
+
+pub(crate) fn toggle_trailing_slash(path: &str) -> Option<String> {
+    if path == "/" {
+        None
+    } else if let Some(path) = path.strip_suffix('/') {
+        Some(path.to_owned())
+    } else {
+        Some(format!("{path}/"))
+    }
+}
+
+fn merge_slashes(path: &str) -> Cow<'_, str> {
+    if !path.contains("//") {
+        return Cow::Borrowed(path);
+    }
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c != '/' || !out.ends_with('/') {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// 解析`.`和`..`（包括百分号编码形式），`..`不会越过根路径。
+fn resolve_dots(path: &str) -> Cow<'_, str> {
+    if !path.split('/').any(|s| is_dot(s) || is_dot_dot(s)) {
+        return Cow::Borrowed(path);
+    }
+
+    let mut segments = Vec::new();
+    let mut trailing = false;
+
+    for segment in path.split('/').skip(1) {
+        trailing = false;
+        if is_dot(segment) {
+            trailing = true;
+        } else if is_dot_dot(segment) {
+            segments.pop();
+            trailing = true;
+        } else {
+            segments.push(segment);
+        }
+    }
+
+    let mut out = format!("/{}", segments.join("/"));
+    if trailing && !out.ends_with('/') {
+        out.push('/');
+    }
+    Cow::Owned(out)
+}
+
+fn is_dot(segment: &str) -> bool {
+    segment == "." || segment.eq_ignore_ascii_case("%2e")
+}
+
+fn is_dot_dot(segment: &str) -> bool {
+    matches!(
+        segment.to_ascii_lowercase().as_str(),
+        ".." | ".%2e" | "%2e." | "%2e%2e"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_slashes, resolve_dots, toggle_trailing_slash};
+
+    #[test]
+    fn slashes() {
+        assert_eq!(merge_slashes("/a//b///c/"), "/a/b/c/");
+        assert_eq!(merge_slashes("//"), "/");
+        assert_eq!(merge_slashes("/a/b"), "/a/b");
+    }
+
+    #[test]
+    fn dots() {
+        assert_eq!(resolve_dots("/a/./b/../c"), "/a/c");
+        assert_eq!(resolve_dots("/../../etc/passwd"), "/etc/passwd");
+        assert_eq!(resolve_dots("/a/%2e%2E/b"), "/b");
+        assert_eq!(resolve_dots("/a/b/.."), "/a/");
+        assert_eq!(resolve_dots("/a/..b"), "/a/..b");
+    }
+
+    #[test]
+    fn trailing_slash() {
+        assert_eq!(toggle_trailing_slash("/a"), Some("/a/".to_owned()));
+        assert_eq!(toggle_trailing_slash("/a/"), Some("/a".to_owned()));
+        assert_eq!(toggle_trailing_slash("/"), None);
+    }
+}
@@ -1,6 +1,7 @@
 use echo_core::http::Extensions;
-use matchit::Params;
+use percent_encoding::percent_decode_str;
 
+use crate::pattern::Pattern;
 use crate::router::PRIVATE_TAIL_PARAM;
 
 /// 路由器提取的路径参数。
@@ -17,26 +18,64 @@ impl PathParams {
     }
 }
 
-pub fn prase_path_params(params: Params) -> (Vec<(String, String)>, Option<String>) {
-    params.iter().fold(
-        (Vec::with_capacity(params.len()), None),
-        |(mut params, mut tail), (k, v)| {
-            if k == PRIVATE_TAIL_PARAM {
-                tail = Some(format!("/{}", if v.starts_with('/') { &v[1..] } else { v }));
-            } else {
-                params.push((k.to_owned(), v.to_owned()));
-            }
-            (params, tail)
-        },
-    )
+impl From<Vec<(String, String)>> for PathParams {
+    fn from(params: Vec<(String, String)>) -> Self {
+        Self(params)
+    }
+}
+
+/// 未经百分号解码的路径参数，供代理等需要原始形式的场景使用。
+#[derive(Debug, Clone)]
+pub struct RawPathParams(Vec<(String, String)>);
+
+impl RawPathParams {
+    pub fn get_ref(&self) -> &Vec<(String, String)> {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<(String, String)> {
+        self.0
+    }
+}
+
+pub fn prase_path_params(params: &[(&str, &str)], pattern: &Pattern) -> Vec<(String, String)> {
+    params
+        .iter()
+        .filter(|(k, _)| *k != PRIVATE_TAIL_PARAM)
+        .map(|&(k, v)| (pattern.name(k).to_owned(), v.to_owned()))
+        .collect()
+}
+
+/// 对参数值进行百分号解码，任一参数无法解码为UTF-8时返回`None`。
+pub fn decode_path_params(params: &[(String, String)]) -> Option<Vec<(String, String)>> {
+    params
+        .iter()
+        .map(|(k, v)| {
+            let v = percent_decode_str(v).decode_utf8().ok()?;
+            Some((k.clone(), v.into_owned()))
+        })
+        .collect()
+}
+
+pub fn is_valid_encoding(s: &str) -> bool {
+    percent_decode_str(s).decode_utf8().is_ok()
 }
 
-pub fn insert_path_params(extensions: &mut Extensions, params: Vec<(String, String)>) {
-    let path_params = if let Some(path_params) = extensions.get_mut::<PathParams>() {
-        path_params
-    } else {
-        extensions.insert(PathParams(vec![]));
-        extensions.get_mut().unwrap()
-    };
-    path_params.0.extend(params);
+pub fn insert_path_params(
+    extensions: &mut Extensions,
+    params: Vec<(String, String)>,
+    raw: Vec<(String, String)>,
+) {
+    match extensions.get_mut::<PathParams>() {
+        Some(path_params) => path_params.0.extend(params),
+        None => {
+            extensions.insert(PathParams(params));
+        }
+    }
+    match extensions.get_mut::<RawPathParams>() {
+        Some(path_params) => path_params.0.extend(raw),
+        None => {
+            extensions.insert(RawPathParams(raw));
+        }
+    }
 }
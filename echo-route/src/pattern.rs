@@ -0,0 +1,314 @@
+use std::fmt;
+
+use crate::router::PRIVATE_TAIL_PARAM;
+
+const PRIVATE_PARAM_PREFIX: &str = "__private__param_";
+
+/// 路径参数的约束，例如`:id<u64>`、`:uuid<uuid>`。
+///
+/// 启用`regex`特性后，无法识别的约束将作为正则表达式，例如`:slug<[a-z0-9-]+>`。
+/// 约束检查失败时继续匹配其他形状中不带约束的路由，例如`/files/:id<u64>`不匹配时回落到`/*`，
+/// 只有带约束的路由的形状不参与回落。
+#[derive(Clone)]
+pub enum Constraint {
+    Builtin(&'static str, fn(&str) -> bool),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Constraint {
+    fn parse(spec: &str) -> Result<Self, String> {
+        macro_rules! builtin {
+            ($($ty:ident),*) => {
+                match spec {
+                    $(stringify!($ty) => return Ok(Constraint::Builtin(stringify!($ty), |s| s.parse::<$ty>().is_ok())),)*
+                    "uuid" => return Ok(Constraint::Builtin("uuid", is_uuid)),
+                    _ => {}
+                }
+            };
+        }
+
+        builtin!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool);
+
+        #[cfg(feature = "regex")]
+        {
+            regex::Regex::new(&format!("^(?:{spec})$"))
+                .map(Constraint::Regex)
+                .map_err(|e| format!("invalid constraint `{spec}` ({e})"))
+        }
+        #[cfg(not(feature = "regex"))]
+        {
+            Err(format!("unknown constraint `{spec}`"))
+        }
+    }
+
+    fn check(&self, value: &str) -> bool {
+        match self {
+            Constraint::Builtin(_, f) => f(value),
+            #[cfg(feature = "regex")]
+            Constraint::Regex(re) => re.is_match(value),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Constraint::Builtin(name, _) => name,
+            #[cfg(feature = "regex")]
+            Constraint::Regex(re) => re.as_str(),
+        }
+    }
+}
+
+impl fmt::Debug for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Constraint").field(&self.as_str()).finish()
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// 注册路径解析后的结果。
+///
+/// 参数名被替换为按位置编号的私有名称，使得参数名或约束不同、形状相同的路径可以共用一个匹配节点。
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    path: String,
+    names: Vec<String>,
+    constraints: Vec<Option<Constraint>>,
+}
+
+impl Pattern {
+    pub fn parse(path: &str, case_insensitive: bool) -> Result<Self, String> {
+        let mut out = String::with_capacity(path.len());
+        let mut names = Vec::new();
+        let mut constraints = Vec::new();
+
+        let mut rest = path;
+
+        while let Some(i) = rest.find([':', '*']) {
+            let kind = &rest[i..=i];
+            push_static(&mut out, &rest[..i], case_insensitive);
+            rest = &rest[i + 1..];
+
+            let end = rest.find(['/', '<']).unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+
+            if name.is_empty() {
+                return Err("parameter must be named".to_owned());
+            }
+
+            if kind == "*" && name == PRIVATE_TAIL_PARAM {
+                out.push_str(kind);
+                out.push_str(name);
+                continue;
+            }
+
+            let constraint = if rest.starts_with('<') {
+                if kind == "*" {
+                    return Err(format!(
+                        "catch-all parameter `{name}` cannot be constrained"
+                    ));
+                }
+                let end = constraint_end(rest)
+                    .ok_or_else(|| format!("unterminated constraint on parameter `{name}`"))?;
+                let spec = &rest[1..end];
+                rest = &rest[end + 1..];
+                if !rest.is_empty() && !rest.starts_with('/') {
+                    return Err(format!(
+                        "constraint on parameter `{name}` must end the segment"
+                    ));
+                }
+                Some(Constraint::parse(spec)?)
+            } else {
+                None
+            };
+
+            out.push_str(kind);
+            out.push_str(PRIVATE_PARAM_PREFIX);
+            out.push_str(&names.len().to_string());
+
+            names.push(name.to_owned());
+            constraints.push(constraint);
+        }
+
+        push_static(&mut out, rest, case_insensitive);
+
+        Ok(Self {
+            path: out,
+            names,
+            constraints,
+        })
+    }
+
+    /// 交给`matchit`的路径。
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_constrained(&self) -> bool {
+        self.constraints.iter().any(Option::is_some)
+    }
+
+    /// 两个形状相同的路径约束完全一致时，后注册的永远无法被匹配到。
+    pub fn same_constraints(&self, other: &Pattern) -> bool {
+        self.constraints == other.constraints
+    }
+
+    pub fn check(&self, params: &[(&str, &str)]) -> bool {
+        params.iter().all(|(k, v)| match self.index(k) {
+            Some(i) => self.constraints[i].as_ref().is_none_or(|c| {
+                percent_encoding::percent_decode_str(v)
+                    .decode_utf8()
+                    .is_ok_and(|v| c.check(&v))
+            }),
+            None => true,
+        })
+    }
+
+    pub fn name<'a>(&'a self, key: &'a str) -> &'a str {
+        self.index(key).map_or(key, |i| &self.names[i])
+    }
+
+    fn index(&self, key: &str) -> Option<usize> {
+        key.strip_prefix(PRIVATE_PARAM_PREFIX)?.parse().ok()
+    }
+}
+
+fn push_static(out: &mut String, s: &str, case_insensitive: bool) {
+    if case_insensitive {
+        out.push_str(&s.to_ascii_lowercase());
+    } else {
+        out.push_str(s);
+    }
+}
+
+/// `s`以`<`开头，返回与之配对的`>`的位置。
+///
+/// 正则表达式中的转义字符和字符类`[...]`视为整体，其中的`<`、`>`不参与配对，
+/// 例如`<[^>]+>`和`<\>+>`。
+fn constraint_end(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                // 紧跟在`[`或`[^`之后的`]`是字面量。
+                chars.next_if(|&(_, c)| c == '^');
+                chars.next_if(|&(_, c)| c == ']');
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    #[test]
+    fn canonical() {
+        let pattern = Pattern::parse("/users/:id<u64>/files/*path", false).unwrap();
+        assert_eq!(
+            pattern.path(),
+            "/users/:__private__param_0/files/*__private__param_1"
+        );
+        assert!(pattern.is_constrained());
+        assert_eq!(pattern.name("__private__param_1"), "path");
+    }
+
+    #[test]
+    fn unconstrained() {
+        let pattern = Pattern::parse("/users/:id", false).unwrap();
+        assert!(!pattern.is_constrained());
+        assert!(Pattern::parse("/static", false).unwrap().names.is_empty());
+    }
+
+    #[test]
+    fn check() {
+        let mut router = matchit::Router::new();
+        let pattern = Pattern::parse("/:id<u64>/:uuid<uuid>", false).unwrap();
+        router.insert(pattern.path(), ()).unwrap();
+
+        let check = |path| {
+            let m = router.at(path).unwrap();
+            pattern.check(&m.params.iter().collect::<Vec<_>>())
+        };
+
+        assert!(check("/42/67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!check("/x/67e55044-10b1-426f-9247-bb680e5fe0c8"));
+        assert!(!check("/42/not-a-uuid"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let pattern = Pattern::parse("/Users/:Id", true).unwrap();
+        assert_eq!(pattern.path(), "/users/:__private__param_0");
+        assert_eq!(pattern.name("__private__param_0"), "Id");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Pattern::parse("/:id<u64", false).is_err());
+        assert!(Pattern::parse("/:id<u64>x", false).is_err());
+        assert!(Pattern::parse("/*path<u64>", false).is_err());
+        #[cfg(not(feature = "regex"))]
+        assert!(Pattern::parse("/:id<nope>", false).is_err());
+        assert!(Pattern::parse("/:<u64>", false).is_err());
+        assert!(Pattern::parse("/:v<[^>]+", false).is_err());
+    }
+
+    #[test]
+    fn constraint_end() {
+        use super::constraint_end;
+
+        assert_eq!(constraint_end("<u64>"), Some(4));
+        assert_eq!(constraint_end("<[^>]+>/x"), Some(6));
+        assert_eq!(constraint_end("<\\>+>"), Some(4));
+        assert_eq!(constraint_end("<[]>]>"), Some(5));
+        assert_eq!(constraint_end("<(?<n>a)>"), Some(8));
+        assert_eq!(constraint_end("<[>"), None);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_with_angle_brackets() {
+        // `\>`在正则表达式中表示词尾。
+        let pattern = Pattern::parse("/:v<[^>]+>/:w<ab\\>>", false).unwrap();
+        assert!(pattern.check(&[("__private__param_0", "a<b"), ("__private__param_1", "ab")]));
+        assert!(!pattern.check(&[("__private__param_0", "a>b")]));
+        assert!(!pattern.check(&[("__private__param_1", "abc")]));
+    }
+}
@@ -2,15 +2,22 @@ use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
+use echo_core::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
 use echo_core::response::IntoResponse;
 use echo_core::service::future::BoxFuture;
 use echo_core::service::{middleware_fn, ArcService, Middleware, Service, ServiceExt};
 use echo_core::{BoxError, Request, Response};
-use matchit::{Match, MatchError};
+use matchit::Match;
 
 use crate::future::RouteFuture;
-use crate::method::{MergeToMethodRouter, MethodRouter};
-use crate::{IntoMethodRoute, MethodRoute, RouteError, RouterError};
+use crate::method::MethodRouter;
+use crate::normalize::{toggle_trailing_slash, PathPolicy, TrailingSlash};
+use crate::pattern::Pattern;
+use crate::util::Nested;
+use crate::{
+    Group, Guard, IntoMethodRoute, MethodRoute, NestedPath, RouteError, RouterError, RouterIssue,
+    RouterReport,
+};
 
 pub const PRIVATE_TAIL_PARAM: &'static str = "__private__tail_param";
 
@@ -26,17 +33,107 @@ impl RouteId {
     }
 }
 
-#[derive(Default)]
+/// 匹配到的路由、路径参数和作用域的前缀与剩余路径。
+type Found = (RouteId, Vec<(String, String)>, Option<Nested>);
+
+/// 注册路径失败的原因，以及与之冲突的已注册路由。
+struct Rejection {
+    error: RouterError,
+    conflicts: Vec<RouteId>,
+}
+
+impl From<RouterError> for Rejection {
+    fn from(error: RouterError) -> Self {
+        Self {
+            error,
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 struct RouterInner {
     id: RouteId,
-    inner: matchit::Router<RouteId>,
+    case_insensitive: bool,
+    inner: matchit::Router<usize>,
+    /// 只包含有不带约束变体的形状，约束检查全部失败时在其中重新匹配。
+    fallback: matchit::Router<usize>,
+    slots: Vec<Vec<(RouteId, Pattern)>>,
+    shape_to_slot: HashMap<String, usize>,
     id_to_path: HashMap<RouteId, Arc<str>>,
     path_to_id: HashMap<Arc<str>, RouteId>,
 }
 
+/// 按形状逐段取出原始路径中的参数值。
+///
+/// 小写转换不改变`/`的位置和各段的长度，每段至多一个参数且延伸到段尾，
+/// 通配参数延伸到路径末尾，因此参数在原始路径中的位置可以由形状确定。
+fn restore_case<'k, 'p, 'v>(
+    shape: &str,
+    path: &'p str,
+    params: impl Iterator<Item = (&'k str, &'v str)>,
+) -> Vec<(&'k str, &'p str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for segment in path.split('/') {
+        segments.push((start, segment));
+        start += segment.len() + 1;
+    }
+
+    let values = shape.split('/').enumerate().filter_map(|(i, shape)| {
+        let offset = shape.find([':', '*'])?;
+        let (start, segment) = *segments.get(i)?;
+        if shape[offset..].starts_with('*') {
+            path.get(start + offset..)
+        } else {
+            segment.get(offset..)
+        }
+    });
+    params.map(|(k, _)| k).zip(values).collect()
+}
+
 impl RouterInner {
-    fn at<'m, 'p>(&'m self, path: &'p str) -> Result<Match<'m, 'p, &'m RouteId>, MatchError> {
-        self.inner.at(path)
+    fn at(&self, path: &str) -> Option<Found> {
+        let lowercase = self.case_insensitive.then(|| path.to_ascii_lowercase());
+        let lookup = lowercase.as_deref().unwrap_or(path);
+
+        // 匹配到的形状的约束检查全部失败时，继续匹配其他不带约束的形状，
+        // 只有约束变体的形状不参与该过程。
+        match self.lookup(&self.inner, path, lookup)? {
+            Ok(found) => Some(found),
+            Err(()) => self.lookup(&self.fallback, path, lookup)?.ok(),
+        }
+    }
+
+    /// 没有匹配的形状时返回`None`，约束检查全部失败时返回`Err`。
+    fn lookup(
+        &self,
+        router: &matchit::Router<usize>,
+        path: &str,
+        lookup: &str,
+    ) -> Option<Result<Found, ()>> {
+        let Match { value, params } = router.at(lookup).ok()?;
+
+        let params = if self.case_insensitive {
+            // 参数值取自原始路径以保留大小写。
+            let shape = self.slots[*value][0].1.path();
+            restore_case(shape, path, params.iter())
+        } else {
+            params.iter().collect::<Vec<_>>()
+        };
+
+        let found = self.slots[*value]
+            .iter()
+            .find(|(_, pattern)| pattern.check(&params))
+            .map(|(id, pattern)| {
+                let nested = params
+                    .iter()
+                    .find(|(k, _)| *k == PRIVATE_TAIL_PARAM)
+                    .map(|(_, tail)| Nested::split(path, tail));
+                let params = crate::params::prase_path_params(&params, pattern);
+                (*id, params, nested)
+            });
+        Some(found.ok_or(()))
     }
 
     fn find(&self, path: &str) -> Option<RouteId> {
@@ -50,30 +147,409 @@ impl RouterInner {
         })
     }
 
-    fn add(&mut self, path: String) -> Result<RouteId, RouterError> {
+    fn add(&mut self, path: String) -> Result<RouteId, Rejection> {
         let id = self.next().ok_or_else(|| RouterError::TooManyPath)?;
+        self.insert(id, path)?;
+        Ok(id)
+    }
+
+    fn insert(&mut self, id: RouteId, path: String) -> Result<(), Rejection> {
+        let pattern = match Pattern::parse(&path, self.case_insensitive) {
+            Ok(pattern) => pattern,
+            Err(message) => return Err(RouterError::InvalidPath { path, message }.into()),
+        };
+
+        let slot = if let Some(slot) = self.shape_to_slot.get(pattern.path()) {
+            *slot
+        } else {
+            let slot = self.slots.len();
+            if let Err(e) = self.inner.insert(pattern.path(), slot) {
+                // `matchit`给出的冲突路径恰好是已注册的形状时，才能确定与之冲突的路由。
+                let conflicts = match &e {
+                    matchit::InsertError::Conflict { with } => self
+                        .shape_to_slot
+                        .get(with)
+                        .map(|slot| self.slots[*slot].iter().map(|(id, _)| *id).collect())
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                return Err(Rejection {
+                    error: RouterError::from_insert_error(path, e),
+                    conflicts,
+                });
+            }
+            self.slots.push(Vec::new());
+            self.shape_to_slot.insert(pattern.path().to_owned(), slot);
+            slot
+        };
+
+        if let Some((id, _)) = self.slots[slot]
+            .iter()
+            .find(|(_, p)| p.same_constraints(&pattern))
+        {
+            return Err(Rejection {
+                error: RouterError::Conflict {
+                    message: format!(
+                        "conflict with previously registered path {}",
+                        self.id_to_path[id]
+                    ),
+                    path,
+                },
+                conflicts: vec![*id],
+            });
+        }
 
-        if let Err(e) = self.inner.insert(&path, id) {
-            return Err(RouterError::from_insert_error(path, e));
+        // 带约束的路径优先匹配，不带约束的路径作为兜底放在最后。
+        let variants = &mut self.slots[slot];
+        if !pattern.is_constrained() && variants.iter().all(|(_, p)| p.is_constrained()) {
+            // 形状已成功插入`inner`，其子集不会冲突。
+            self.fallback
+                .insert(pattern.path(), slot)
+                .expect("fallback shapes are a subset of registered shapes");
+        }
+        if pattern.is_constrained() {
+            let index = variants
+                .iter()
+                .position(|(_, p)| !p.is_constrained())
+                .unwrap_or(variants.len());
+            variants.insert(index, (id, pattern));
+        } else {
+            variants.push((id, pattern));
         }
 
         let path: Arc<str> = path.into();
         self.id_to_path.insert(id, path.clone());
         self.path_to_id.insert(path, id);
 
-        Ok(id)
+        Ok(())
+    }
+
+    fn remove(&mut self, id: RouteId) {
+        if let Some(path) = self.id_to_path.remove(&id) {
+            self.path_to_id.remove(&path);
+            // `matchit`不支持删除，只能重建。
+            self.rebuild(self.case_insensitive)
+                .expect("rebuilding a subset of valid routes cannot fail");
+        }
+    }
+
+    fn set_case_insensitive(&mut self, case_insensitive: bool) -> Result<(), RouterError> {
+        if self.case_insensitive == case_insensitive {
+            return Ok(());
+        }
+        self.rebuild(case_insensitive)
+    }
+
+    fn rebuild(&mut self, case_insensitive: bool) -> Result<(), RouterError> {
+        let mut paths = self.id_to_path.drain().collect::<Vec<_>>();
+        paths.sort_by_key(|(id, _)| *id);
+
+        *self = RouterInner {
+            id: self.id,
+            case_insensitive,
+            ..Default::default()
+        };
+
+        for (id, path) in paths {
+            self.insert(id, path.as_ref().to_owned())
+                .map_err(|rejection| rejection.error)?;
+        }
+
+        Ok(())
     }
 }
 
-enum Endpoint<T> {
+#[derive(Clone)]
+pub(crate) enum Endpoint<T> {
     Route(T),
     Scope(T),
 }
 
-#[derive(Default)]
-pub struct Router {
+impl<T> Endpoint<T> {
+    fn get_ref(&self) -> &T {
+        match self {
+            Endpoint::Route(t) | Endpoint::Scope(t) => t,
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        match self {
+            Endpoint::Route(t) | Endpoint::Scope(t) => t,
+        }
+    }
+
+    fn into_inner(self) -> T {
+        match self {
+            Endpoint::Route(t) | Endpoint::Scope(t) => t,
+        }
+    }
+
+    fn is_scope(&self) -> bool {
+        matches!(self, Endpoint::Scope(_))
+    }
+}
+
+/// [`Router`]和[`LocalRouter`](crate::LocalRouter)共用的路由表，`S`为装箱后的服务类型。
+#[derive(Clone)]
+pub(crate) struct RouterCore<S> {
     inner: RouterInner,
-    table: HashMap<RouteId, Endpoint<MethodRouter>>,
+    pub(crate) policy: PathPolicy,
+    table: HashMap<RouteId, Endpoint<MethodRouter<S>>>,
+    pub(crate) source: Option<Arc<str>>,
+    pub(crate) override_conflicts: bool,
+}
+
+impl<S> Default for RouterCore<S> {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            policy: Default::default(),
+            table: HashMap::new(),
+            source: None,
+            override_conflicts: false,
+        }
+    }
+}
+
+/// 一次路由匹配的结果。
+pub(crate) enum Dispatch<'a, S> {
+    Found(&'a MethodRouter<S>, Request),
+    Redirect(Response),
+    Error(RouteError),
+}
+
+impl<S: Clone> RouterCore<S> {
+    pub(crate) fn set_case_insensitive(&mut self, enable: bool) -> Result<(), RouterError> {
+        self.inner.set_case_insensitive(enable)
+    }
+
+    pub(crate) fn route(&mut self, path: &str, service: MethodRoute<S>) -> Result<(), RouterError> {
+        let path = route_path(path)?;
+        let source = self.source.clone();
+        self.add_route(path, Endpoint::Route(service.into_method_router(source)))
+    }
+
+    pub(crate) fn scope(&mut self, path: &str, service: MethodRoute<S>) -> Result<(), RouterError> {
+        let path = scope_path(path)?;
+        let source = self.source.clone();
+        self.add_route(path, Endpoint::Scope(service.into_method_router(source)))
+    }
+
+    pub(crate) fn merge(&mut self, other: Self) -> Result<(), RouterError> {
+        for (path, endpoint) in other.into_endpoints() {
+            self.add_route(path, endpoint)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn merge_all(&mut self, other: Self) -> Result<(), RouterReport> {
+        let mut report = RouterReport::default();
+        for (path, endpoint) in other.into_endpoints() {
+            if let Err(issue) = self.register(path, endpoint) {
+                report.push(issue);
+            }
+        }
+        if report.is_empty() {
+            Ok(())
+        } else {
+            Err(report)
+        }
+    }
+
+    /// 按注册顺序返回所有路由，使合并结果和报告的顺序是确定的。
+    pub(crate) fn map_services<F>(&mut self, mut f: F)
+    where
+        F: FnMut(S) -> S,
+    {
+        for endpoint in self.table.values_mut() {
+            endpoint.get_mut().map_services(&mut f);
+        }
+    }
+
+    fn into_endpoints(self) -> Vec<(String, Endpoint<MethodRouter<S>>)> {
+        let mut endpoints = self.table.into_iter().collect::<Vec<_>>();
+        endpoints.sort_by_key(|(id, _)| *id);
+        endpoints
+            .into_iter()
+            .map(|(id, endpoint)| (self.inner.id_to_path[&id].as_ref().to_owned(), endpoint))
+            .collect()
+    }
+
+    /// 移除路由，`method`为`None`时移除该路径下的所有服务。
+    pub(crate) fn remove_route(&mut self, path: &str, method: Option<&Method>) -> bool {
+        match route_path(path) {
+            Ok(path) => self.remove_path(&path, method),
+            Err(_) => false,
+        }
+    }
+
+    pub(crate) fn remove_scope(&mut self, path: &str) -> bool {
+        match scope_path(path) {
+            Ok(path) => self.remove_path(&path, None),
+            Err(_) => false,
+        }
+    }
+
+    fn remove_path(&mut self, path: &str, method: Option<&Method>) -> bool {
+        let Some(id) = self.inner.find(path) else {
+            return false;
+        };
+        let remove_all = match (method, self.table.get_mut(&id)) {
+            (Some(method), Some(Endpoint::Route(router))) => {
+                if !router.remove(method) {
+                    return false;
+                }
+                router.is_empty()
+            }
+            (Some(_), _) => return false,
+            (None, _) => true,
+        };
+        if remove_all {
+            self.table.remove(&id);
+            self.inner.remove(id);
+        }
+        true
+    }
+
+    fn add_route(
+        &mut self,
+        path: String,
+        endpoint: Endpoint<MethodRouter<S>>,
+    ) -> Result<(), RouterError> {
+        self.register(path, endpoint)
+            .map_err(RouterIssue::into_error)
+    }
+
+    fn register(
+        &mut self,
+        path: String,
+        endpoint: Endpoint<MethodRouter<S>>,
+    ) -> Result<(), RouterIssue> {
+        let source = endpoint.get_ref().source();
+
+        let id = match self.add_path(path.clone()) {
+            Ok(id) => id,
+            Err(rejection) if self.override_conflicts && !rejection.conflicts.is_empty() => {
+                for id in rejection.conflicts {
+                    self.table.remove(&id);
+                    self.inner.remove(id);
+                }
+                self.add_path(path.clone())
+                    .map_err(|rejection| self.issue(rejection, source.clone()))?
+            }
+            Err(rejection) => return Err(self.issue(rejection, source)),
+        };
+
+        let replace = self.override_conflicts;
+        let entry = self.table.entry(id).or_insert_with(|| match endpoint {
+            Endpoint::Route(_) => Endpoint::Route(Default::default()),
+            Endpoint::Scope(_) => Endpoint::Scope(Default::default()),
+        });
+
+        if entry.is_scope() != endpoint.is_scope() {
+            if !replace {
+                return Err(RouterIssue::new(
+                    RouterError::Conflict {
+                        path,
+                        message: format!("conflict with previously registered route"),
+                    },
+                    source,
+                    entry.get_ref().source(),
+                ));
+            }
+            *entry = match endpoint {
+                Endpoint::Route(_) => Endpoint::Route(Default::default()),
+                Endpoint::Scope(_) => Endpoint::Scope(Default::default()),
+            };
+        }
+
+        entry
+            .get_mut()
+            .merge(endpoint.into_inner(), replace)
+            .map_err(|conflict| {
+                let message = match conflict.method {
+                    Some(method) => {
+                        format!("conflict with previously registered `{method}` HTTP method")
+                    }
+                    None => format!("conflict with previously registered any HTTP method"),
+                };
+                RouterIssue::new(
+                    RouterError::Conflict { path, message },
+                    conflict.source,
+                    conflict.previous,
+                )
+            })
+    }
+
+    fn issue(&self, rejection: Rejection, source: Option<Arc<str>>) -> RouterIssue {
+        let previous = rejection
+            .conflicts
+            .iter()
+            .find_map(|id| self.table.get(id))
+            .and_then(|endpoint| endpoint.get_ref().source());
+        RouterIssue::new(rejection.error, source, previous)
+    }
+
+    fn add_path(&mut self, path: String) -> Result<RouteId, Rejection> {
+        let id = if let Some(id) = self.inner.find(&path) {
+            id
+        } else {
+            self.inner.add(path)?
+        };
+        Ok(id)
+    }
+
+    pub(crate) fn dispatch(&self, mut request: Request) -> Dispatch<'_, S> {
+        if let Some(path) = self.policy.normalize(request.uri().path()) {
+            crate::uri::insert_original_uri(&mut request);
+            crate::util::replace_request_path(&mut request, &path);
+        }
+
+        let mut found = self.inner.at(request.uri().path());
+
+        if found.is_none() && self.policy.trailing_slash != TrailingSlash::Strict {
+            if let Some(path) = toggle_trailing_slash(request.uri().path()) {
+                if let Some(f) = self.inner.at(&path) {
+                    if let Some(status) = self.policy.trailing_slash.redirect_status() {
+                        return Dispatch::Redirect(redirect(&request, status, &path));
+                    }
+                    crate::uri::insert_original_uri(&mut request);
+                    crate::util::replace_request_path(&mut request, &path);
+                    found = Some(f);
+                }
+            }
+        }
+
+        let Some((id, raw, nested)) = found else {
+            return Dispatch::Error(RouteError::not_found(request));
+        };
+
+        // 作用域的剩余路径仍作为URI使用，保持编码形式，只检查其能否解码。
+        let params = crate::params::decode_path_params(&raw).filter(|_| {
+            nested
+                .as_ref()
+                .is_none_or(|nested| crate::params::is_valid_encoding(&nested.tail))
+        });
+        let Some(params) = params else {
+            return Dispatch::Error(RouteError::invalid_encoding(request));
+        };
+        crate::params::insert_path_params(request.extensions_mut(), params, raw);
+        crate::uri::insert_matched_path(request.extensions_mut(), &self.inner.id_to_path[&id]);
+
+        match self.table.get(&id) {
+            Some(Endpoint::Route(service)) => Dispatch::Found(service, request),
+            Some(Endpoint::Scope(service)) => {
+                crate::util::nest_request(&mut request, nested.unwrap());
+                Dispatch::Found(service, request)
+            }
+            None => Dispatch::Error(RouteError::not_found(request)),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Router {
+    core: RouterCore<ArcService<Request, Response, BoxError>>,
 }
 
 impl Router {
@@ -81,6 +557,45 @@ impl Router {
         Default::default()
     }
 
+    pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.core.policy.trailing_slash = trailing_slash;
+        self
+    }
+
+    /// 匹配前将连续的`/`合并为一个。
+    pub fn merge_slashes(mut self, enable: bool) -> Self {
+        self.core.policy.merge_slashes = enable;
+        self
+    }
+
+    /// 匹配前解析路径中的`.`和`..`。
+    pub fn resolve_dots(mut self, enable: bool) -> Self {
+        self.core.policy.resolve_dots = enable;
+        self
+    }
+
+    /// 路径的静态部分不区分ASCII大小写，路径参数保留原始大小写。
+    pub fn case_insensitive(self, enable: bool) -> Self {
+        self.try_case_insensitive(enable).unwrap()
+    }
+
+    pub fn try_case_insensitive(mut self, enable: bool) -> Result<Self, RouterError> {
+        self.core.set_case_insensitive(enable)?;
+        Ok(self)
+    }
+
+    /// 之后注册的路由都标记为来自`source`（例如模块名），冲突时会同时报告双方的来源。
+    pub fn source(mut self, source: impl Into<Arc<str>>) -> Self {
+        self.core.source = Some(source.into());
+        self
+    }
+
+    /// 路径或方法冲突时，后注册的路由覆盖先注册的路由，而不是报错。
+    pub fn override_conflicts(mut self, enable: bool) -> Self {
+        self.core.override_conflicts = enable;
+        self
+    }
+
     pub fn route<S>(self, path: &str, service: S) -> Self
     where
         S: IntoMethodRoute,
@@ -92,7 +607,7 @@ impl Router {
         self.try_route(path, service).unwrap()
     }
 
-    pub fn try_route<S>(self, path: &str, service: S) -> Result<Self, RouterError>
+    pub fn try_route<S>(mut self, path: &str, service: S) -> Result<Self, RouterError>
     where
         S: IntoMethodRoute,
         S::Service: Service<Request> + Send + Sync + 'static,
@@ -100,25 +615,13 @@ impl Router {
         <S::Service as Service<Request>>::Error: Into<BoxError>,
         <S::Service as Service<Request>>::Future: Send,
     {
-        if !path.starts_with('/') {
-            return Err(RouterError::InvalidPath {
-                path: path.to_owned(),
-                message: format!("path must start with a `/`"),
-            });
-        }
-        let path = if path.ends_with('*') {
-            format!("{path}{PRIVATE_TAIL_PARAM}")
-        } else {
-            path.into()
-        };
-        self.add_route(
+        self.core.route(
             path,
-            Endpoint::Route(
-                service
-                    .into_method_route()
-                    .with(middleware_fn(Self::into_arc_service)),
-            ),
-        )
+            service
+                .into_method_route()
+                .with(middleware_fn(Self::into_arc_service)),
+        )?;
+        Ok(self)
     }
 
     pub fn scope<S>(self, path: &str, service: S) -> Self
@@ -132,7 +635,7 @@ impl Router {
         self.try_scope(path, service).unwrap()
     }
 
-    pub fn try_scope<S>(self, path: &str, service: S) -> Result<Self, RouterError>
+    pub fn try_scope<S>(mut self, path: &str, service: S) -> Result<Self, RouterError>
     where
         S: IntoMethodRoute,
         S::Service: Service<Request> + Send + Sync + 'static,
@@ -140,25 +643,13 @@ impl Router {
         <S::Service as Service<Request>>::Error: Into<BoxError>,
         <S::Service as Service<Request>>::Future: Send,
     {
-        if !path.starts_with('/') {
-            return Err(RouterError::InvalidPath {
-                path: path.to_owned(),
-                message: format!("path must start with a `/`"),
-            });
-        }
-        let path = if path.ends_with('/') {
-            format!("{path}*{PRIVATE_TAIL_PARAM}")
-        } else {
-            format!("{path}/*{PRIVATE_TAIL_PARAM}")
-        };
-        self.add_route(
+        self.core.scope(
             path,
-            Endpoint::Scope(
-                service
-                    .into_method_route()
-                    .with(middleware_fn(Self::into_arc_service)),
-            ),
-        )
+            service
+                .into_method_route()
+                .with(middleware_fn(Self::into_arc_service)),
+        )?;
+        Ok(self)
     }
 
     pub fn mount<S>(self, route: impl Into<Route<S>>) -> Self
@@ -181,68 +672,74 @@ impl Router {
         route.into().mount_to(self)
     }
 
-    pub fn merge(self, other: Router) -> Self {
-        self.try_merge(other).unwrap()
+    pub fn group<F>(self, prefix: &str, f: F) -> Self
+    where
+        F: FnOnce(Group) -> Group,
+    {
+        self.try_group(prefix, f).unwrap()
     }
 
-    pub fn try_merge(mut self, other: Router) -> Result<Self, RouterError> {
-        for (id, endpoint) in other.table {
-            self = self.add_route(other.inner.id_to_path[&id].as_ref().to_owned(), endpoint)?;
+    pub fn try_group<F>(mut self, prefix: &str, f: F) -> Result<Self, RouterError>
+    where
+        F: FnOnce(Group) -> Group,
+    {
+        for (path, endpoint) in f(Group::new(prefix)).into_routes()? {
+            self = match endpoint {
+                Endpoint::Route(service) => self.try_route(&path, service)?,
+                Endpoint::Scope(service) => self.try_scope(&path, service)?,
+            };
         }
         Ok(self)
     }
 
-    fn add_route<T: MergeToMethodRouter>(
-        mut self,
-        path: String,
-        endpoint: Endpoint<T>,
-    ) -> Result<Self, RouterError> {
-        let id = self.add_path(path.clone())?;
-
-        let result = match endpoint {
-            Endpoint::Route(service) => {
-                let Endpoint::Route(router) = self.table.entry(id).or_insert_with(|| Endpoint::Route(Default::default())) else {
-                    return Err(RouterError::Conflict {
-                        path,
-                        message: format!("conflict with previously registered route"),
-                    })
-                };
-                service.merge_to(router)
-            }
-            Endpoint::Scope(service) => {
-                let Endpoint::Scope(router) = self.table.entry(id).or_insert_with(|| Endpoint::Scope(Default::default())) else {
-                    return Err(RouterError::Conflict {
-                        path,
-                        message: format!("conflict with previously registered route"),
-                    })
-                };
-                service.merge_to(router)
-            }
-        };
+    pub fn merge(self, other: Router) -> Self {
+        self.try_merge_all(other)
+            .unwrap_or_else(|report| panic!("{report}"))
+    }
 
-        result.map_err(|method| {
-            let message = match method {
-                Some(method) => {
-                    format!("conflict with previously registered `{method}` HTTP method")
-                }
-                None => format!("conflict with previously registered any HTTP method"),
-            };
-            RouterError::Conflict { path, message }
-        })?;
+    pub fn try_merge(mut self, other: Router) -> Result<Self, RouterError> {
+        self.core.merge(other.core)?;
+        Ok(self)
+    }
 
+    /// 与[`Router::try_merge`]相同，但不会在第一个错误处停止，而是收集所有冲突和无效路径。
+    pub fn try_merge_all(mut self, other: Router) -> Result<Self, RouterReport> {
+        self.core.merge_all(other.core)?;
         Ok(self)
     }
 
-    fn add_path(&mut self, path: String) -> Result<RouteId, RouterError> {
-        let id = if let Some(id) = self.inner.find(&path) {
-            id
-        } else {
-            self.inner.add(path)?
-        };
-        Ok(id)
+    /// 检查能否合并`other`，不修改任何一方。
+    pub fn validate_merge(&self, other: &Router) -> Result<(), RouterReport> {
+        self.clone().try_merge_all(other.clone()).map(|_| ())
     }
 
-    fn into_arc_service<S>(service: S) -> ArcService<Request, Response, BoxError>
+    /// 为已注册的每个服务添加中间件，之后注册的路由不受影响。
+    ///
+    /// 中间件在匹配之后、处理程序之前运行，可以从请求的扩展中读取路由元数据，
+    /// 也能看到处理程序返回的错误；未匹配的请求不经过它。
+    pub fn route_layer<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware<ArcService<Request, Response, BoxError>> + Clone,
+        M::Service: Service<Request> + Send + Sync + 'static,
+        <M::Service as Service<Request>>::Response: IntoResponse,
+        <M::Service as Service<Request>>::Error: Into<BoxError>,
+        <M::Service as Service<Request>>::Future: Send,
+    {
+        self.core
+            .map_services(|service| Self::into_arc_service(middleware.clone().transform(service)));
+        self
+    }
+
+    /// 移除路由，`method`为`None`时移除该路径下的所有服务。
+    pub(crate) fn remove_route(&mut self, path: &str, method: Option<&Method>) -> bool {
+        self.core.remove_route(path, method)
+    }
+
+    pub(crate) fn remove_scope(&mut self, path: &str) -> bool {
+        self.core.remove_scope(path)
+    }
+
+    pub(crate) fn into_arc_service<S>(service: S) -> ArcService<Request, Response, BoxError>
     where
         S: Service<Request> + Send + Sync + 'static,
         S::Response: IntoResponse,
@@ -269,29 +766,69 @@ impl Service<Request> for Router {
     type Error = BoxError;
     type Future = RouteFuture<BoxFuture<'static, Result<Response, BoxError>>>;
 
-    fn call(&self, mut request: Request) -> Self::Future {
-        match self.inner.at(request.uri().path()) {
-            Ok(Match { value, params }) => {
-                let (params, tail) = crate::params::prase_path_params(params);
-                crate::params::insert_path_params(request.extensions_mut(), params);
-                match self.table.get(value) {
-                    Some(Endpoint::Route(service)) => service.call(request),
-                    Some(Endpoint::Scope(service)) => {
-                        crate::util::replace_request_path(&mut request, &tail.unwrap());
-                        service.call(request)
-                    }
-                    None => RouteFuture::Error {
-                        err: Some(RouteError::not_found(request).into()),
-                    },
-                }
-            }
-            Err(_) => RouteFuture::Error {
-                err: Some(RouteError::not_found(request).into()),
+    fn call(&self, request: Request) -> Self::Future {
+        match self.core.dispatch(request) {
+            Dispatch::Found(service, request) => service.call(request),
+            Dispatch::Redirect(response) => RouteFuture::Future {
+                fut: Box::pin(std::future::ready(Ok(response))),
+                matched_path: None,
+                meta: Default::default(),
+            },
+            Dispatch::Error(err) => RouteFuture::Error {
+                err: Some(err.into()),
             },
         }
     }
 }
 
+fn route_path(path: &str) -> Result<String, RouterError> {
+    if !path.starts_with('/') {
+        return Err(RouterError::InvalidPath {
+            path: path.to_owned(),
+            message: format!("path must start with a `/`"),
+        });
+    }
+    Ok(if path.ends_with('*') {
+        format!("{path}{PRIVATE_TAIL_PARAM}")
+    } else {
+        path.into()
+    })
+}
+
+fn scope_path(path: &str) -> Result<String, RouterError> {
+    if !path.starts_with('/') {
+        return Err(RouterError::InvalidPath {
+            path: path.to_owned(),
+            message: format!("path must start with a `/`"),
+        });
+    }
+    Ok(if path.ends_with('/') {
+        format!("{path}*{PRIVATE_TAIL_PARAM}")
+    } else {
+        format!("{path}/*{PRIVATE_TAIL_PARAM}")
+    })
+}
+
+fn redirect(request: &Request, status: StatusCode, path: &str) -> Response {
+    let prefix = request
+        .extensions()
+        .get::<NestedPath>()
+        .map(NestedPath::path)
+        .unwrap_or_default();
+
+    let location = match request.uri().query() {
+        Some(query) => format!("{prefix}{path}?{query}"),
+        None => format!("{prefix}{path}"),
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Ok(location) = HeaderValue::try_from(location) {
+        headers.insert(header::LOCATION, location);
+    }
+
+    (status, headers).into_response()
+}
+
 #[derive(Debug, Clone)]
 pub struct Route<S> {
     path: String,
@@ -309,6 +846,37 @@ impl<S> Route<S> {
         }
     }
 
+    pub fn guard<G: Guard>(self, guard: G) -> Self {
+        Route {
+            path: self.path,
+            service: self.service.guard(guard),
+        }
+    }
+
+    pub fn produces(self, mime: &str) -> Self {
+        Route {
+            path: self.path,
+            service: self.service.produces(mime),
+        }
+    }
+
+    pub fn consumes(self, mime: &str) -> Self {
+        Route {
+            path: self.path,
+            service: self.service.consumes(mime),
+        }
+    }
+
+    pub fn meta<T>(self, value: T) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        Route {
+            path: self.path,
+            service: self.service.meta(value),
+        }
+    }
+
     pub fn with<T>(self, middleware: T) -> Route<T::Service>
     where
         T: Middleware<S>,
@@ -319,6 +887,10 @@ impl<S> Route<S> {
         }
     }
 
+    pub(crate) fn into_parts(self) -> (String, MethodRoute<S>) {
+        (self.path, self.service)
+    }
+
     fn mount_to(self, router: Router) -> Result<Router, RouterError>
     where
         S: Service<Request> + Send + Sync + 'static,
@@ -329,3 +901,378 @@ impl<S> Route<S> {
         router.try_route(&self.path, self.service)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use echo_core::body::BodyExt;
+    use echo_core::http::{HeaderValue, Method, StatusCode};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{middleware_fn, service_fn, ArcService, Service, ServiceExt};
+    use echo_core::{BoxError, Request, Response};
+
+    use super::Router;
+    use crate::{
+        get, post, MatchedPath, Negotiated, NestedPath, OriginalUri, PathParams, RawPathParams,
+        RouteError, RouteErrorKind, TrailingSlash,
+    };
+
+    async fn a(_: Request) -> Result<&'static str, Infallible> {
+        Ok("a")
+    }
+
+    async fn b(_: Request) -> Result<&'static str, Infallible> {
+        Ok("b")
+    }
+
+    fn users() -> Router {
+        Router::new()
+            .source("users")
+            .route("/users", get(service_fn(a)))
+            .route("/users/:id", get(service_fn(a)))
+            .scope("/static", service_fn(a))
+    }
+
+    fn admin() -> Router {
+        Router::new()
+            .source("admin")
+            .route("/users", get(service_fn(b)).add(Method::POST))
+            .route("/users/:name", get(service_fn(b)))
+            .route("/static/*", service_fn(b))
+            .route("/admin", post(service_fn(b)))
+    }
+
+    #[test]
+    fn report() {
+        let report = users().validate_merge(&admin()).unwrap_err();
+        assert_eq!(report.len(), 3);
+        for issue in report.issues() {
+            assert_eq!(issue.location(), Some("admin"));
+            assert_eq!(issue.previous_location(), Some("users"));
+        }
+        assert!(users().try_merge(admin()).is_err());
+    }
+
+    #[tokio::test]
+    async fn override_conflicts() {
+        let router = users()
+            .override_conflicts(true)
+            .try_merge_all(admin())
+            .unwrap();
+
+        for path in ["/users", "/users/42", "/static/x", "/admin"] {
+            let method = if path == "/admin" { "POST" } else { "GET" };
+            let request = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(&body[..], b"b", "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn percent_decoding() {
+        async fn params(request: Request) -> Result<String, Infallible> {
+            let params = request.extensions().get::<PathParams>().unwrap();
+            let raw = request.extensions().get::<RawPathParams>().unwrap();
+            Ok(format!("{}|{}", params.get_ref()[0].1, raw.get_ref()[0].1))
+        }
+
+        let router = Router::new().route("/files/:name", service_fn(params));
+
+        let request = Request::builder()
+            .uri("/files/a%20b")
+            .body(Default::default())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        assert_eq!(&body[..], b"a b|a%20b");
+
+        let request = Request::builder()
+            .uri("/files/%ff")
+            .body(Default::default())
+            .unwrap();
+        let error = router.call(request).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<RouteError>().unwrap().kind(),
+            RouteErrorKind::InvalidEncoding
+        );
+    }
+
+    #[tokio::test]
+    async fn meta() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Permission(&'static str);
+
+        async fn permission(request: Request) -> Result<&'static str, Infallible> {
+            Ok(request.extensions().get::<Permission>().map_or("", |p| p.0))
+        }
+
+        async fn overridden(_: Request) -> Result<Response, Infallible> {
+            let mut response = "admin".into_response();
+            response.extensions_mut().insert(Permission("admin"));
+            Ok(response)
+        }
+
+        let router = Router::new()
+            .route("/a", get(service_fn(permission)).meta(Permission("read")))
+            .route("/a", post(service_fn(permission)).meta(Permission("write")))
+            .route("/b", service_fn(permission))
+            .route("/c", get(service_fn(overridden)).meta(Permission("read")));
+
+        // 包裹整个路由器的中间件从响应的扩展中读取元数据。
+        let service = router.map_response(|mut response: Response| {
+            let permission = response
+                .extensions()
+                .get::<Permission>()
+                .map_or("", |p| p.0);
+            let value = HeaderValue::from_static(permission);
+            response.headers_mut().insert("x-permission", value);
+            response
+        });
+
+        for (method, path, expected, outer) in [
+            (Method::GET, "/a", "read", "read"),
+            (Method::POST, "/a", "write", "write"),
+            (Method::GET, "/b", "", ""),
+            (Method::GET, "/c", "admin", "admin"),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Default::default())
+                .unwrap();
+            let response = service.call(request).await.unwrap();
+            assert_eq!(response.headers()["x-permission"], outer);
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(&body[..], expected.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn route_layer() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Permission(&'static str);
+
+        async fn ok(_: Request) -> Result<&'static str, Infallible> {
+            Ok("ok")
+        }
+
+        async fn fail(_: Request) -> Result<&'static str, std::io::Error> {
+            Err(std::io::Error::other("fail"))
+        }
+
+        // 只允许只读操作，在处理程序之前根据元数据拒绝请求。
+        let read_only = middleware_fn(|inner: ArcService<Request, Response, BoxError>| {
+            service_fn(move |request: Request| {
+                let inner = inner.clone();
+                async move {
+                    match request.extensions().get::<Permission>() {
+                        Some(Permission("write")) => Ok(StatusCode::FORBIDDEN.into_response()),
+                        _ => inner.call(request).await,
+                    }
+                }
+            })
+        });
+
+        let router = Router::new()
+            .route("/a", get(service_fn(ok)).meta(Permission("read")))
+            .route("/a", post(service_fn(ok)).meta(Permission("write")))
+            .route("/b", get(service_fn(fail)).meta(Permission("read")))
+            .route_layer(read_only)
+            .route("/c", post(service_fn(ok)).meta(Permission("write")));
+
+        for (method, path, status) in [
+            (Method::GET, "/a", StatusCode::OK),
+            (Method::POST, "/a", StatusCode::FORBIDDEN),
+            (Method::POST, "/c", StatusCode::OK),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            assert_eq!(response.status(), status, "{path}");
+        }
+
+        let request = Request::builder()
+            .uri("/b")
+            .body(Default::default())
+            .unwrap();
+        let error = router.call(request).await.unwrap_err();
+        assert_eq!(error.to_string(), "fail");
+    }
+
+    #[tokio::test]
+    async fn negotiation() {
+        async fn negotiated(request: Request) -> Result<String, Infallible> {
+            let mime = request.extensions().get::<Negotiated>().unwrap();
+            Ok(mime.0.to_string())
+        }
+
+        let router = Router::new()
+            .route(
+                "/items",
+                get(service_fn(negotiated)).produces("application/json"),
+            )
+            .route(
+                "/items",
+                get(service_fn(negotiated)).produces("application/vnd.acme.v2+json"),
+            )
+            .route("/items", post(service_fn(a)).consumes("application/json"));
+
+        for (accept, expected) in [
+            (None, Ok("application/json")),
+            (Some("*/*"), Ok("application/json")),
+            (
+                Some("application/json;q=0.5, application/vnd.acme.v2+json"),
+                Ok("application/vnd.acme.v2+json"),
+            ),
+            (Some("text/html"), Err(RouteErrorKind::NotAcceptable)),
+        ] {
+            let mut request = Request::builder().uri("/items");
+            if let Some(accept) = accept {
+                request = request.header("accept", accept);
+            }
+            let request = request.body(Default::default()).unwrap();
+            let result = match router.call(request).await {
+                Ok(response) => {
+                    let body = response.into_body().collect().bytes().await.unwrap();
+                    Ok(String::from_utf8(body.to_vec()).unwrap())
+                }
+                Err(e) => Err(e.downcast_ref::<RouteError>().unwrap().kind()),
+            };
+            assert_eq!(result.as_deref().map_err(|e| *e), expected);
+        }
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/items")
+            .header("content-type", "text/plain")
+            .body(Default::default())
+            .unwrap();
+        let error = router.call(request).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<RouteError>().unwrap().kind(),
+            RouteErrorKind::UnsupportedMediaType
+        );
+    }
+
+    #[tokio::test]
+    async fn matched_path() {
+        let router = Router::new()
+            .route("/files/*", get(service_fn(a)))
+            .scope("/api", users());
+
+        for (uri, expected) in [
+            ("/files/a/b", "/files/*"),
+            ("/api/users/1", "/api/users/:id"),
+            ("/api/static/a.js", "/api/static/*"),
+        ] {
+            let request = Request::builder()
+                .uri(uri)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            let matched_path = response.extensions().get::<MatchedPath>().unwrap();
+            assert_eq!(matched_path.as_str(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn nested_path() {
+        async fn uris(request: Request) -> Result<String, Infallible> {
+            let nested = request.extensions().get::<NestedPath>().unwrap();
+            let original = request.extensions().get::<OriginalUri>().unwrap();
+            Ok(format!(
+                "{:?} {} {}",
+                nested.get_ref(),
+                request.uri(),
+                original.0
+            ))
+        }
+
+        let v1 = Router::new()
+            .trailing_slash(TrailingSlash::Ignore)
+            .route("/users/:id", get(service_fn(uris)))
+            .route("/posts/", get(service_fn(uris)));
+        let router = Router::new()
+            .trailing_slash(TrailingSlash::Ignore)
+            .scope("/api", Router::new().scope("/v1/", v1))
+            .scope("/static/", service_fn(uris));
+
+        for (uri, expected) in [
+            (
+                "/api/v1/users/1?q=1",
+                r#"["/api", "/v1"] /users/1?q=1 /api/v1/users/1?q=1"#,
+            ),
+            ("/api/v1/posts", r#"["/api", "/v1"] /posts/ /api/v1/posts"#),
+            ("/static/a/b.js", r#"["/static"] /a/b.js /static/a/b.js"#),
+        ] {
+            let request = Request::builder()
+                .uri(uri)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, expected, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn case_insensitive_params() {
+        async fn params(request: Request) -> Result<String, Infallible> {
+            let params = request.extensions().get::<PathParams>().unwrap();
+            let nested = request.extensions().get::<NestedPath>();
+            Ok(format!(
+                "{:?} {:?} {}",
+                params.get_ref(),
+                nested.map(|n| n.get_ref()),
+                request.uri()
+            ))
+        }
+
+        let router = Router::new()
+            .case_insensitive(true)
+            .route("/Users/:id/File_:name", get(service_fn(params)))
+            .scope("/Static/", service_fn(params));
+
+        for (uri, expected) in [
+            (
+                "/users/AbC/file_X.TXT",
+                r#"[("id", "AbC"), ("name", "X.TXT")] None /users/AbC/file_X.TXT"#,
+            ),
+            ("/STATIC/A/b.JS", r#"[] Some(["/STATIC"]) /A/b.JS"#),
+        ] {
+            let request = Request::builder()
+                .uri(uri)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, expected, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn constraint_fallthrough() {
+        let router = Router::new()
+            .route("/files/:id<u64>", get(service_fn(a)))
+            .route("/*", get(service_fn(b)));
+
+        for (uri, expected) in [("/files/1", "a"), ("/files/abc", "b"), ("/files/1/2", "b")] {
+            let request = Request::builder()
+                .uri(uri)
+                .body(Default::default())
+                .unwrap();
+            let response = router.call(request).await.unwrap();
+            let body = response.into_body().collect().bytes().await.unwrap();
+            assert_eq!(body, expected, "{uri}");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use echo_core::http::{Extensions, Uri};
+use echo_core::Request;
+
+use crate::router::PRIVATE_TAIL_PARAM;
+
+/// 进入第一个作用域之前的原始请求URI。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalUri(pub Uri);
+
+/// 嵌套路由器已剥离的路径前缀，按剥离顺序排列。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NestedPath(Vec<String>);
+
+impl NestedPath {
+    pub fn get_ref(&self) -> &Vec<String> {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<String> {
+        self.0
+    }
+
+    pub fn path(&self) -> String {
+        self.0.concat()
+    }
+}
+
+/// 匹配到的路由模板，例如`/users/:id`，嵌套的路由器中为拼接后的完整模板。
+///
+/// 路由器同时将其保存在请求和响应的扩展中，外层中间件可以从响应中读取。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatchedPath(Arc<str>);
+
+impl MatchedPath {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+pub fn insert_original_uri(request: &mut Request) {
+    if request.extensions().get::<OriginalUri>().is_none() {
+        let uri = request.uri().clone();
+        request.extensions_mut().insert(OriginalUri(uri));
+    }
+}
+
+pub fn push_nested_path(extensions: &mut Extensions, prefix: String) {
+    if let Some(nested_path) = extensions.get_mut::<NestedPath>() {
+        nested_path.0.push(prefix);
+    } else {
+        extensions.insert(NestedPath(vec![prefix]));
+    }
+}
+
+pub fn insert_matched_path(extensions: &mut Extensions, path: &str) {
+    let path = path.strip_suffix(PRIVATE_TAIL_PARAM).unwrap_or(path);
+    let path: Arc<str> = match extensions.get::<MatchedPath>() {
+        // 外层为作用域，去掉其末尾的`/*`后拼接。
+        Some(prefix) => {
+            let prefix = prefix.as_str().trim_end_matches('*').trim_end_matches('/');
+            format!("{prefix}{path}").into()
+        }
+        None => path.into(),
+    };
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("route", &*path);
+    extensions.insert(MatchedPath(path));
+}
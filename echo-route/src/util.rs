@@ -10,6 +10,34 @@ pub fn try_downcast<T: 'static, K: 'static>(k: K) -> Result<T, K> {
     }
 }
 
+/// 作用域匹配的结果，`prefix`为匹配的前缀，`tail`为以`/`开头的剩余路径。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nested {
+    pub prefix: String,
+    pub tail: String,
+}
+
+impl Nested {
+    /// `tail`为匹配时的尾部参数，通配参数总是位于路径末尾，因此是`path`的后缀。
+    pub fn split(path: &str, tail: &str) -> Self {
+        debug_assert!(path.ends_with(tail));
+        let prefix = path
+            .get(..path.len().saturating_sub(tail.len()))
+            .unwrap_or_default();
+        Self {
+            prefix: prefix.strip_suffix('/').unwrap_or(prefix).to_owned(),
+            tail: format!("/{}", tail.strip_prefix('/').unwrap_or(tail)),
+        }
+    }
+}
+
+pub fn nest_request(request: &mut Request, nested: Nested) {
+    crate::uri::insert_original_uri(request);
+    crate::uri::push_nested_path(request.extensions_mut(), nested.prefix);
+
+    replace_request_path(request, &nested.tail);
+}
+
 pub fn replace_request_path(request: &mut Request, path: &str) {
     let uri = request.uri_mut();
 
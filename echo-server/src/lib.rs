@@ -5,6 +5,8 @@ mod compat;
 mod graceful_shutdown;
 use graceful_shutdown::GracefulShutdown;
 
+pub use echo_core::{LocalAddr, RemoteAddr};
+
 use std::convert::Infallible;
 use std::future::Future;
 use std::net::SocketAddr;
@@ -25,6 +27,8 @@ struct Options {
 pub struct Server {
     options: Options,
     http1: http1::Builder,
+    #[cfg(feature = "tracing")]
+    trace: Option<echo_middleware::trace::TraceMiddleware>,
 }
 
 impl Server {
@@ -32,9 +36,22 @@ impl Server {
         Self {
             options: Options { addr },
             http1: http1::Builder::new(),
+            #[cfg(feature = "tracing")]
+            trace: Some(Default::default()),
         }
     }
 
+    /// 自定义每个请求的span，默认启用；传入`None`时不创建请求的span，
+    /// 例如服务中已经使用了`TraceMiddleware`。连接的span始终会创建。
+    #[cfg(feature = "tracing")]
+    pub fn trace(
+        mut self,
+        trace: impl Into<Option<echo_middleware::trace::TraceMiddleware>>,
+    ) -> Self {
+        self.trace = trace.into();
+        self
+    }
+
     pub fn cfg_http1<F>(mut self, f: F) -> Self
     where
         F: FnOnce(&mut http1::Builder),
@@ -66,6 +83,15 @@ impl Server {
     {
         tokio::pin!(signal);
 
+        let service = service.map_response(|response: S::Response| response.into_response());
+
+        #[cfg(feature = "tracing")]
+        let service = match self.trace.clone() {
+            Some(trace) => service.with(trace).boxed_arc(),
+            None => service.boxed_arc(),
+        };
+
+        #[cfg(not(feature = "tracing"))]
         let service = service.boxed_arc();
 
         let graceful = GracefulShutdown::new();
@@ -77,7 +103,7 @@ impl Server {
                     break timeout;
                 }
                 conn = listener.accept() => {
-                    let (conn, _) = conn?;
+                    let (conn, _addr) = conn?;
 
                     let service = service.clone();
                     let service = extract_addr(service, &conn);
@@ -87,6 +113,12 @@ impl Server {
                     let conn = self.http1.serve_connection(conn, service).with_upgrades();
                     let conn = graceful.watch(conn);
 
+                    #[cfg(feature = "tracing")]
+                    let conn = tracing::Instrument::instrument(
+                        conn,
+                        tracing::info_span!("connection", remote_addr = %_addr),
+                    );
+
                     tokio::spawn(conn);
                 }
             }
@@ -98,12 +130,6 @@ impl Server {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct LocalAddr(pub SocketAddr);
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct RemoteAddr(pub SocketAddr);
-
 fn extract_addr<S, R>(
     service: S,
     conn: &TcpStream,
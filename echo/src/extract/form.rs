@@ -68,4 +68,12 @@ impl fmt::Display for ExtractFormError {
     }
 }
 
-impl std::error::Error for ExtractFormError {}
+impl std::error::Error for ExtractFormError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractFormError::UnsupportedContentType => None,
+            ExtractFormError::FailedToReadBody(e) => Some(&**e),
+            ExtractFormError::FailedToDeserialize(e) => Some(e),
+        }
+    }
+}
@@ -63,4 +63,12 @@ impl fmt::Display for ExtractJsonError {
     }
 }
 
-impl std::error::Error for ExtractJsonError {}
+impl std::error::Error for ExtractJsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractJsonError::UnsupportedContentType => None,
+            ExtractJsonError::FailedToReadBody(e) => Some(&**e),
+            ExtractJsonError::FailedToDeserialize(e) => Some(e),
+        }
+    }
+}
@@ -18,8 +18,111 @@ pub use header::{header, ExtractHeaderError};
 pub use json::{json, ExtractJsonError};
 #[cfg(feature = "multipart")]
 pub use multipart::multipart;
-pub use path::{path, ExtractPathError};
+pub use path::{path, path_as, ExtractPathError};
 pub use query::{query, ExtractQueryError};
 pub use stream::stream;
 #[cfg(feature = "ws")]
 pub use ws::ws;
+
+#[cfg(all(test, feature = "body-limit"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::error::Error;
+    use std::future::Future;
+
+    use echo_core::body::{BodyExt, LengthLimitError};
+    use echo_core::http::{header, Method};
+    use echo_core::response::IntoResponse;
+    use echo_core::service::{service_fn, Service, ServiceExt};
+    use echo_core::{Request, Response};
+
+    use crate::middleware::body_limit::BodyLimitMiddleware;
+
+    fn is_length_limit(err: &(dyn Error + 'static)) -> bool {
+        let mut next = Some(err);
+        while let Some(err) = next {
+            if err.is::<LengthLimitError>() {
+                return true;
+            }
+            next = err.source();
+        }
+        false
+    }
+
+    /// 在限制为16字节的请求体上执行提取器，返回错误链中是否有`LengthLimitError`。
+    async fn extract<F, Fut>(content_type: &'static str, body: String, f: F) -> bool
+    where
+        F: Fn(Request) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let service = service_fn(|request: Request| {
+            let fut = f(request);
+            async move { Ok::<_, Infallible>(fut.await.to_string().into_response()) }
+        })
+        .with(BodyLimitMiddleware::new(16));
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap();
+        let response: Response = service.call(request).await.unwrap();
+        let body = response.into_body().collect().bytes().await.unwrap();
+        &body[..] == b"true"
+    }
+
+    #[tokio::test]
+    async fn bytes() {
+        let limited = extract("text/plain", "x".repeat(32), |mut request| async move {
+            let err = super::bytes(&mut request).await.unwrap_err();
+            is_length_limit(&*err)
+        });
+        assert!(limited.await);
+    }
+
+    #[tokio::test]
+    async fn json() {
+        let body = format!("{{\"name\":\"{}\"}}", "x".repeat(32));
+        let limited = extract("application/json", body, |mut request| async move {
+            let err = super::json::<HashMap<String, String>>(&mut request)
+                .await
+                .unwrap_err();
+            is_length_limit(&err)
+        });
+        assert!(limited.await);
+    }
+
+    #[tokio::test]
+    async fn form() {
+        let body = format!("name={}", "x".repeat(32));
+        let content_type = "application/x-www-form-urlencoded";
+        let limited = extract(content_type, body, |mut request| async move {
+            let err = super::form::<HashMap<String, String>>(&mut request)
+                .await
+                .unwrap_err();
+            is_length_limit(&err)
+        });
+        assert!(limited.await);
+    }
+
+    #[cfg(feature = "multipart")]
+    #[tokio::test]
+    async fn multipart() {
+        let body = format!(
+            "--X\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n{}\r\n--X--\r\n",
+            "x".repeat(32)
+        );
+        let content_type = "multipart/form-data; boundary=X";
+        let limited = extract(content_type, body, |mut request| async move {
+            let mut multipart = super::multipart(&mut request).unwrap();
+            let err = match multipart.next().await {
+                Ok(Some(field)) => field.bytes().await.unwrap_err(),
+                Ok(None) => return false,
+                Err(err) => err,
+            };
+            is_length_limit(&err)
+        });
+        assert!(limited.await);
+    }
+}
@@ -3,6 +3,9 @@ use std::str::FromStr;
 
 use echo_core::{BoxError, Request};
 use echo_route::PathParams;
+use serde::de::DeserializeOwned;
+
+mod de;
 
 pub fn path<T>(request: &Request, name: &str) -> Result<T, ExtractPathError>
 where
@@ -22,6 +25,19 @@ where
     )
 }
 
+/// 将所有路径参数反序列化为`T`。
+///
+/// `T`可以是结构体、元组、映射，只有一个参数时也可以是单个值。
+pub fn path_as<T>(request: &Request) -> Result<T, ExtractPathError>
+where
+    T: DeserializeOwned,
+{
+    let params = crate::extract::extension(request)
+        .map(|params: &PathParams| params.get_ref().as_slice())
+        .unwrap_or_default();
+    T::deserialize(de::PathDeserializer::new(params)).map_err(Into::into)
+}
+
 fn find<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
     crate::extract::extension(request)
         .map(|params: &PathParams| params.get_ref())
@@ -33,6 +49,7 @@ fn find<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
 pub enum ExtractPathError {
     MissingParam { name: String },
     InvalidParam { name: String, source: BoxError },
+    FailedToDeserialize(String),
 }
 
 impl fmt::Display for ExtractPathError {
@@ -44,8 +61,80 @@ impl fmt::Display for ExtractPathError {
             ExtractPathError::InvalidParam { name, source } => {
                 write!(f, "invalid path param `{name}` ({source})")
             }
+            ExtractPathError::FailedToDeserialize(message) => {
+                write!(f, "failed to deserialize path params ({message})")
+            }
         }
     }
 }
 
 impl std::error::Error for ExtractPathError {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use echo_core::Request;
+    use echo_route::PathParams;
+    use serde::Deserialize;
+
+    use super::{path_as, ExtractPathError};
+
+    fn with_params(params: &[(&str, &str)]) -> Request {
+        let mut request = Request::default();
+        let params = params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        request.extensions_mut().insert(PathParams::from(params));
+        request
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Params {
+        id: u64,
+        name: String,
+    }
+
+    #[test]
+    fn deserialize() {
+        let request = with_params(&[("id", "42"), ("name", "a b")]);
+
+        let params: Params = path_as(&request).unwrap();
+        assert_eq!(
+            params,
+            Params {
+                id: 42,
+                name: "a b".into()
+            }
+        );
+
+        let (id, name): (u64, String) = path_as(&request).unwrap();
+        assert_eq!((id, name.as_str()), (42, "a b"));
+
+        let map: HashMap<String, String> = path_as(&request).unwrap();
+        assert_eq!(map["name"], "a b");
+    }
+
+    #[test]
+    fn errors() {
+        let request = with_params(&[("id", "x"), ("name", "y")]);
+
+        match path_as::<Params>(&request) {
+            Err(ExtractPathError::InvalidParam { name, .. }) => assert_eq!(name, "id"),
+            other => panic!("{other:?}"),
+        }
+        match path_as::<HashMap<String, u64>>(&request) {
+            Err(ExtractPathError::InvalidParam { name, .. }) => assert_eq!(name, "id"),
+            other => panic!("{other:?}"),
+        }
+        match path_as::<(String,)>(&request) {
+            Err(ExtractPathError::FailedToDeserialize(_)) => {}
+            other => panic!("{other:?}"),
+        }
+        match path_as::<Params>(&with_params(&[("id", "1")])) {
+            Err(ExtractPathError::MissingParam { name }) => assert_eq!(name, "name"),
+            other => panic!("{other:?}"),
+        }
+    }
+}
@@ -9,6 +9,56 @@ pub use echo_core::*;
 pub mod middleware {
     pub use echo_middleware::core::*;
     pub use echo_middleware::util::*;
+
+    #[cfg(feature = "cors")]
+    pub mod cors {
+        pub use echo_middleware::cors::*;
+    }
+
+    #[cfg(any(
+        feature = "compression-gzip",
+        feature = "compression-deflate",
+        feature = "compression-br",
+        feature = "compression-zstd"
+    ))]
+    pub mod compression {
+        pub use echo_middleware::compression::*;
+    }
+
+    #[cfg(feature = "timeout")]
+    pub mod timeout {
+        pub use echo_middleware::timeout::*;
+    }
+
+    #[cfg(feature = "request-id")]
+    pub mod request_id {
+        pub use echo_middleware::request_id::*;
+    }
+
+    #[cfg(feature = "access-log")]
+    pub mod access_log {
+        pub use echo_middleware::access_log::*;
+    }
+
+    #[cfg(feature = "rate-limit")]
+    pub mod rate_limit {
+        pub use echo_middleware::rate_limit::*;
+    }
+
+    #[cfg(feature = "concurrency-limit")]
+    pub mod concurrency_limit {
+        pub use echo_middleware::concurrency_limit::*;
+    }
+
+    #[cfg(feature = "body-limit")]
+    pub mod body_limit {
+        pub use echo_middleware::body_limit::*;
+    }
+
+    #[cfg(feature = "tracing")]
+    pub mod trace {
+        pub use echo_middleware::trace::*;
+    }
 }
 
 pub mod route {
@@ -23,6 +73,14 @@ pub mod server {
 #[cfg(feature = "macros")]
 pub use echo_macros::route;
 
+#[cfg(all(feature = "macros", feature = "fs"))]
+pub use echo_macros::embed_dir;
+
+#[cfg(feature = "fs")]
+pub mod fs {
+    pub use echo_fs::*;
+}
+
 #[cfg(feature = "ws")]
 pub mod ws {
     pub use echo_ws::*;
@@ -31,7 +31,7 @@ fn app() -> impl Service<
         .with(CatchErrorMiddleware::new(handle_error))
 }
 
-#[echo::route("/div/:n/:d", method = "GET")]
+#[echo::route("/div/:n<u64>/:d<u64>", method = "GET")]
 async fn div(req: Request) -> Result<impl IntoResponse, BoxError> {
     let n = extract::path::<u64>(&req, "n")?;
     let d = extract::path::<u64>(&req, "d")?;
@@ -49,6 +49,12 @@ async fn handle_error(err: BoxError) -> Result<Response, Infallible> {
             RouteErrorKind::NotFound => StatusCode::NOT_FOUND,
             // 自定义405响应
             RouteErrorKind::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            // 自定义406响应
+            RouteErrorKind::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            // 自定义415响应
+            RouteErrorKind::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            // 路径中的百分号编码无效
+            RouteErrorKind::InvalidEncoding => StatusCode::BAD_REQUEST,
         };
         return Ok(status_code.into_response());
     }